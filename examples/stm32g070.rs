@@ -8,7 +8,7 @@ extern crate panic_halt; // you can put a breakpoint on `rust_begin_unwind` to c
 
 use cortex_m_rt::entry;
 
-use embedded_hal as hal;
+use embedded_hal_02 as hal;
 use hal::digital::v2::OutputPin;
 
 use stm32g0xx_hal::{
@@ -21,14 +21,16 @@ use stm32g0xx_hal::{
     rcc
 };
 
-use ssd1362::{self, display::DisplayRotation};
+use embedded_hal_bus::spi::ExclusiveDevice;
+
+use ssd1362::{self, compat::Compat, display::DisplayRotation};
 
 use embedded_graphics::{
-    fonts::{Font6x8, Text, Font24x32},
-    pixelcolor::BinaryColor,
+    mono_font::{ascii::{FONT_10X20, FONT_6X10}, MonoTextStyle, MonoTextStyleBuilder},
+    pixelcolor::Gray4,
     prelude::*,
-    primitives::{Circle},
-    style::{PrimitiveStyle, TextStyle, TextStyleBuilder},
+    primitives::{Circle, PrimitiveStyle},
+    text::Text,
 };
 
 
@@ -104,43 +106,36 @@ fn main() -> ! {
     delay.delay(1.ms());
     rst.set_high().unwrap();
 
-    // send configuration bytes
-    cs.set_low().unwrap();
-
     writeln!(usart, "Turn on VCC!").unwrap();
 
-    let spi_interface = ssd1362::interface::SpiInterface::new(spi, cs, dc);
+    // the SPI peripheral as embedded-hal 1.0 `SpiDevice` with its own chip select
+    let spi_device = ExclusiveDevice::new_no_delay(Compat(spi), Compat(cs)).unwrap();
+    let spi_interface = display_interface_spi::SPIInterface::new(spi_device, Compat(dc));
     let mut display = ssd1362::display::Display::new(spi_interface, DisplayRotation::Rotate0);
     display.init().unwrap();
-    display.clear(BinaryColor::Off);
+    display.clear(0);
     display.on().unwrap();
     display.flush().unwrap();
 
+    let text_style = MonoTextStyle::new(&FONT_6X10, Gray4::WHITE);
+    Text::new("Hello, Jitter!", Point::new(4, 10), text_style).draw(&mut display).unwrap();
 
-    display.write_char('@', 0,0).unwrap();
-
-
-    display.write_char('=', 1,1).unwrap();
-
-    display.write_char('?', 2,2).unwrap();
-
-
-    display.write_string("Hello, Jitter!", 4, 4).unwrap();
+    display.flush().unwrap();
 
     delay.delay(2000.ms());
 
-    let c = Circle::new(Point::new(20, 20), 12).into_styled(PrimitiveStyle::with_fill(BinaryColor::On));
-    let t = Text::new("Hello Rust!", Point::new(120, 16))
-        .into_styled(TextStyle::new(Font6x8, BinaryColor::On));
-
-    c.draw(&mut display);
-    t.draw(&mut display);
-
+    let c = Circle::new(Point::new(20, 20), 12).into_styled(PrimitiveStyle::with_fill(Gray4::new(8)));
+    let t = Text::new("Hello Rust!", Point::new(120, 16), MonoTextStyle::new(&FONT_6X10, Gray4::WHITE));
 
+    c.draw(&mut display).unwrap();
+    t.draw(&mut display).unwrap();
 
-    let style = TextStyleBuilder::new(Font24x32).background_color(BinaryColor::On).text_color(BinaryColor::Off).build();
-    Text::new("YES!", Point::new(120, 30))
-    .into_styled(style).draw(&mut display);
+    let style = MonoTextStyleBuilder::new()
+        .font(&FONT_10X20)
+        .background_color(Gray4::WHITE)
+        .text_color(Gray4::BLACK)
+        .build();
+    Text::new("YES!", Point::new(120, 40), style).draw(&mut display).unwrap();
 
 
     display.flush().unwrap();
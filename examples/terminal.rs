@@ -8,7 +8,7 @@ extern crate panic_halt; // you can put a breakpoint on `rust_begin_unwind` to c
 
 use cortex_m_rt::entry;
 
-use embedded_hal as hal;
+use embedded_hal_02 as hal;
 use hal::digital::v2::OutputPin;
 
 use stm32g0xx_hal::{
@@ -21,7 +21,9 @@ use stm32g0xx_hal::{
     rcc
 };
 
-use ssd1362::{self, display::DisplayRotation, terminal};
+use embedded_hal_bus::spi::ExclusiveDevice;
+
+use ssd1362::{self, compat::Compat, display::DisplayRotation, power::PowerControl, terminal};
 
 #[entry]
 fn main() -> ! {
@@ -32,7 +34,7 @@ fn main() -> ! {
     let rcc_cfg = rcc::Config::pll().pll_cfg(pll_cfg);
     let mut rcc = dp.RCC.freeze(rcc_cfg);
 
-    let mut delay = dp.TIM15.delay(&mut rcc);
+    let mut delay = Compat(dp.TIM15.delay(&mut rcc));
 
     let gpioa = dp.GPIOA.split(&mut rcc);
     let gpiob = dp.GPIOB.split(&mut rcc);
@@ -45,7 +47,7 @@ fn main() -> ! {
     // let mut led = gpioa.pa5.into_push_pull_output();
     let mut led_g = gpioa.pa7.into_push_pull_output();
     let mut led_r = gpiob.pb0.into_push_pull_output();
-    let mut en_16v = gpioa.pa1.into_push_pull_output();
+    let en_16v = gpioa.pa1.into_push_pull_output();
 
 
     let mut cs = gpiob.pb4.into_push_pull_output(); // blue 13
@@ -56,8 +58,7 @@ fn main() -> ! {
     let mut dc = gpiob.pb7.into_push_pull_output(); // orange
     dc.set_low().unwrap();
 
-    let mut rst = gpiob.pb6.into_push_pull_output();
-    rst.set_high().unwrap();
+    let rst = gpiob.pb6.into_push_pull_output();
 
     let mut usart = dp
     .USART1 // tx      // rx
@@ -72,7 +73,7 @@ fn main() -> ! {
     writeln!(usart3, "Hello stm32g0\n").unwrap();
 
     led_g.set_high().unwrap();
-    delay.delay(500.ms());
+    delay.0.delay(500.ms());
     led_g.set_low().unwrap();
 
     let sck = gpiob.pb3; // yellow 10
@@ -85,17 +86,16 @@ fn main() -> ! {
         &mut rcc);
 
 
-    // do power on reset
-    rst.set_low().unwrap();
-    delay.delay(1.ms());
-    rst.set_high().unwrap();
-
+    // the SPI peripheral as embedded-hal 1.0 `SpiDevice` with its own chip select
+    let spi_device = ExclusiveDevice::new_no_delay(Compat(spi), Compat(cs)).unwrap();
+    let interface = display_interface_spi::SPIInterface::new(spi_device, Compat(dc));
+    let mut display = ssd1362::display::Display::new(interface, DisplayRotation::Rotate180);
 
+    // reset, init and turn on VCC in the right order
     writeln!(usart, "Turn on VCC!").unwrap();
-    en_16v.set_high().unwrap();
+    let mut power = PowerControl::new(Compat(rst), Compat(en_16v));
+    power.power_on(&mut display, &mut delay).unwrap();
 
-    let interface = display_interface_spi::SPIInterface::new(spi, dc, cs);
-    let display = ssd1362::display::Display::new(interface, DisplayRotation::Rotate180);
     writeln!(usart, "create terminal..").unwrap();
     let font = terminal::Font6x8 {};
     let mut terminal = terminal::TerminalView::new(display, font);
@@ -162,6 +162,6 @@ fn main() -> ! {
                 step = 0;
             }
         }
-        delay.delay(50.ms());
+        delay.0.delay(50.ms());
     }
 }
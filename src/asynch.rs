@@ -0,0 +1,366 @@
+//! Async display driver
+//!
+//! `AsyncDisplay` has the same API as `Display`, but sends through an `AsyncWriteOnlyDataCommand`
+//! interface. Framebuffer uploads then run on DMA while other tasks keep going, e.g. with embassy.
+//! Drawing into the framebuffer does not touch the bus and is not async.
+//! The commands and the areas to upload are planned by the wrapped `Display`, only the
+//! transport is async.
+//!
+//! ```ignore
+//! let mut display = AsyncDisplay::new(interface, DisplayRotation::Rotate0);
+//! display.init().await?;
+//! display.set_pixel(10, 10, 0xF);
+//! display.flush().await?;
+//! ```
+
+use display_interface::{AsyncWriteOnlyDataCommand, DataFormat::U8};
+#[cfg(feature = "graphics")]
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Size},
+    pixelcolor::Gray4,
+    Pixel,
+};
+
+use crate::command::{Command, CommandBuffer, FadeMode, RemapConfig, ScrollDirection, ScrollInterval};
+use crate::config::DisplayConfig;
+use crate::display::{brightness_to_contrast, ram_window, Display, DisplayRotation};
+use crate::error::Error;
+use crate::framebuffer::Area;
+use crate::grayscale::GrayscaleTable;
+use crate::registers::Registers;
+
+/// `Display` for async interfaces
+pub struct AsyncDisplay<DI> {
+    display: Display<DI>,
+}
+
+impl<DI> AsyncDisplay<DI>
+where
+    DI: AsyncWriteOnlyDataCommand,
+{
+    pub fn new(iface: DI, rotation: DisplayRotation) -> Self {
+        AsyncDisplay { display: Display::new(iface, rotation) }
+    }
+
+    /// Create a display with custom init parameters, see `DisplayConfig`
+    pub fn with_config(iface: DI, rotation: DisplayRotation, config: DisplayConfig) -> Self {
+        AsyncDisplay { display: Display::with_config(iface, rotation, config) }
+    }
+
+    /// See `Display::with_registers`
+    pub fn with_registers(iface: DI, rotation: DisplayRotation, config: DisplayConfig, registers: Registers) -> Self {
+        AsyncDisplay { display: Display::with_registers(iface, rotation, config, registers) }
+    }
+
+    /// Send the init sequence from the `DisplayConfig` in a single transaction
+    pub async fn init(&mut self) -> Result<(), Error> {
+        let commands = self.display.init_commands()?;
+        self.send_commands(commands.iter().flatten().copied()).await?;
+        self.display.registers.mark_initialized();
+        Ok(())
+    }
+
+    /// Init parameters of the display
+    pub fn config(&self) -> &DisplayConfig {
+        self.display.config()
+    }
+
+    /// Re-map settings in use, from the `DisplayConfig` or derived from the rotation
+    pub fn remap(&self) -> RemapConfig {
+        self.display.remap()
+    }
+
+    pub fn dimensions(&self) -> (usize, usize) {
+        self.display.dimensions()
+    }
+
+    /// See `Display::blank`
+    pub async fn blank(&mut self) -> Result<(), Error> {
+        self.display.blank_framebuffer();
+        self.flush().await
+    }
+
+    /// See `Display::set_draw_area`
+    pub async fn set_draw_area(&mut self, start: (u8, u8), end: (u8, u8)) -> Result<(), Error> {
+        let area = self.display.panel_area(start, end)?;
+        self.set_ram_area(area).await
+    }
+
+    async fn set_ram_area(&mut self, area: Area) -> Result<(), Error> {
+        self.send_commands(ram_window(area)).await
+    }
+
+    /// See `Display::draw`
+    pub async fn draw(&mut self, buffer: &[u8]) -> Result<(), Error> {
+        self.display.iface.send_data(U8(buffer)).await?;
+        Ok(())
+    }
+
+    /// Turn the display on.
+    pub async fn on(&mut self) -> Result<(), Error> {
+        self.send_command(Command::DisplayOn(true)).await
+    }
+
+    /// Turn the display off.
+    pub async fn off(&mut self) -> Result<(), Error> {
+        self.send_command(Command::DisplayOn(false)).await
+    }
+
+    pub async fn scroll(&mut self, offset: u8) -> Result<(), Error> {
+        self.send_command(Command::display_offset(offset)?).await
+    }
+
+    /// See `Display::start_horizontal_scroll`
+    pub async fn start_horizontal_scroll(&mut self, direction: ScrollDirection, start: (u8, u8), end: (u8, u8), interval: ScrollInterval) -> Result<(), Error> {
+        let commands = self.display.start_scroll_commands(direction, start, end, interval)?;
+        self.send_commands(commands.iter().flatten().copied()).await
+    }
+
+    /// See `Display::stop_horizontal_scroll`
+    pub async fn stop_horizontal_scroll(&mut self) -> Result<(), Error> {
+        match self.display.stop_scroll_command() {
+            Some(command) => self.send_command(command).await,
+            None => Ok(()),
+        }
+    }
+
+    /// See `Display::set_fade_mode`
+    pub async fn set_fade_mode(&mut self, mode: FadeMode, interval: u8) -> Result<(), Error> {
+        self.send_command(Command::fade_blink(mode, interval)?).await
+    }
+
+    /// Set the contrast, higher is brighter. Stops a running fade.
+    pub async fn set_contrast(&mut self, contrast: u8) -> Result<(), Error> {
+        self.display.fade = None;
+        self.send_contrast(contrast).await
+    }
+
+    /// Current contrast
+    pub fn contrast(&self) -> u8 {
+        self.display.contrast()
+    }
+
+    /// See `Display::registers`
+    pub fn registers(&self) -> &Registers {
+        self.display.registers()
+    }
+
+    /// Set the brightness in percent (0 - 100) of the maximum contrast. Stops a running fade.
+    pub async fn set_brightness(&mut self, percent: u8) -> Result<(), Error> {
+        self.set_contrast(brightness_to_contrast(percent)).await
+    }
+
+    /// See `Display::start_fade`
+    pub fn start_fade(&mut self, target: u8, ticks: u16) {
+        self.display.start_fade(target, ticks);
+    }
+
+    /// See `Display::fade_tick`
+    pub async fn fade_tick(&mut self) -> Result<bool, Error> {
+        if let Some(contrast) = self.display.next_fade_step() {
+            self.send_contrast(contrast).await?;
+        }
+        Ok(self.display.fade.is_some())
+    }
+
+    async fn send_contrast(&mut self, contrast: u8) -> Result<(), Error> {
+        self.send_command(Command::Contrast(contrast)).await
+    }
+
+    /// Set the pulse widths of the gray levels. See `GrayscaleTable` for presets.
+    pub async fn set_grayscale_table(&mut self, table: &GrayscaleTable) -> Result<(), Error> {
+        self.send_command(Command::GrayScale(table.widths())).await
+    }
+
+    /// Go back to the default linear gray scale table of the display
+    pub async fn set_default_grayscale(&mut self) -> Result<(), Error> {
+        self.send_command(Command::DefaultGrayScale()).await
+    }
+
+    /// See `Display::send_command`
+    pub async fn send_command(&mut self, command: Command) -> Result<(), Error> {
+        command.send_async(&mut self.display.iface).await?;
+        self.display.registers.record(command);
+        Ok(())
+    }
+
+    /// See `Display::send_commands`
+    pub async fn send_commands<I>(&mut self, commands: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = Command>,
+        I::IntoIter: Clone,
+    {
+        let commands = commands.into_iter();
+        let mut buffer = CommandBuffer::new();
+        buffer.extend(commands.clone())?;
+        buffer.send_async(&mut self.display.iface).await?;
+
+        commands.for_each(|command| self.display.registers.record(command));
+        Ok(())
+    }
+
+    /// See `Display::reapply_state`
+    pub async fn reapply_state(&mut self) -> Result<(), Error> {
+        let (config, resume) = self.display.state_commands(true)?;
+        self.send_commands(config.iter().chain(resume.iter()).flatten().copied()).await
+    }
+
+    /// See `Display::refresh_config`
+    pub async fn refresh_config(&mut self) -> Result<(), Error> {
+        let (config, resume) = self.display.state_commands(false)?;
+        self.send_commands(config.iter().flatten().copied()).await?;
+
+        self.display.framebuffer.mark_dirty(Area::FULL);
+        self.flush().await?;
+
+        self.send_commands(resume.iter().flatten().copied()).await
+    }
+
+    /// See `Display::set_pixel`
+    pub fn set_pixel(&mut self, x: usize, y: usize, level: u8) {
+        self.display.set_pixel(x, y, level);
+    }
+
+    /// See `Display::get_pixel`
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<u8> {
+        self.display.get_pixel(x, y)
+    }
+
+    /// Fill the whole framebuffer with a single gray level
+    pub fn clear(&mut self, level: u8) {
+        self.display.clear(level);
+    }
+
+    /// See `Display::release`
+    pub fn release(self) -> DI {
+        self.display.release()
+    }
+
+    /// See `Display::into_parts`
+    pub fn into_parts(self) -> (DI, Registers) {
+        self.display.into_parts()
+    }
+
+    /// Upload the parts of the framebuffer that were modified since the last flush, see `Display::flush`
+    pub async fn flush(&mut self) -> Result<(), Error> {
+        while let Some(mut upload) = self.display.next_upload()? {
+            self.set_ram_area(upload.area()).await?;
+            let Display { iface, framebuffer, .. } = &mut self.display;
+            while let Some(data) = upload.next_chunk(framebuffer) {
+                iface.send_data(U8(data)).await?;
+            }
+            self.display.framebuffer.clean(upload.area());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<DI> DrawTarget for AsyncDisplay<DI> {
+    type Color = Gray4;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        self.display.draw_iter(pixels)
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        DrawTarget::clear(&mut self.display, color)
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<DI> OriginDimensions for AsyncDisplay<DI> {
+    fn size(&self) -> Size {
+        self.display.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framebuffer::{RAM_COLUMNS, RAM_ROWS};
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+    use display_interface::DisplayError;
+    use std::vec::Vec;
+
+    /// Interface that records everything sent to it, never has to wait
+    #[derive(Default)]
+    struct Recorder {
+        commands: Vec<u8>,
+        data: Vec<u8>,
+    }
+
+    impl AsyncWriteOnlyDataCommand for Recorder {
+        async fn send_commands(&mut self, cmd: display_interface::DataFormat<'_>) -> Result<(), DisplayError> {
+            match cmd {
+                U8(bytes) => self.commands.extend_from_slice(bytes),
+                _ => return Err(DisplayError::DataFormatNotImplemented),
+            }
+            Ok(())
+        }
+
+        async fn send_data(&mut self, buf: display_interface::DataFormat<'_>) -> Result<(), DisplayError> {
+            match buf {
+                U8(bytes) => self.data.extend_from_slice(bytes),
+                _ => return Err(DisplayError::DataFormatNotImplemented),
+            }
+            Ok(())
+        }
+    }
+
+    /// Run a future that completes without waiting
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        match future.as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future is waiting"),
+        }
+    }
+
+    #[test]
+    fn it_sends_the_same_as_the_blocking_display() {
+        let mut display = AsyncDisplay::new(Recorder::default(), DisplayRotation::Rotate0);
+        block_on(display.init()).unwrap();
+
+        assert_eq!(
+            display.display.iface.commands,
+            [
+                0xAB, 0x01, 0xAD, 0x9E, 0x15, 0x00, 0x7F, 0x75, 0x00, 0x3F, 0xA0, 0x54, 0xA1, 0x00,
+                0xA2, 0x00, 0xA4, 0xA8, 0x3F, 0xB1, 0x11, 0xB3, 0xF0, 0xB9, 0xBC, 0x04, 0xBE, 0x05,
+                0x81, 0x7F
+            ]
+        );
+
+        block_on(display.flush()).unwrap();
+        display.display.iface = Recorder::default();
+
+        display.set_pixel(20, 5, 0x1);
+        display.set_pixel(23, 6, 0x2);
+        block_on(display.flush()).unwrap();
+        assert_eq!(display.display.iface.commands, [0x15, 10, 11, 0x75, 5, 6]);
+        assert_eq!(display.display.iface.data, [0x10, 0x00, 0x00, 0x02]);
+
+        display.display.iface = Recorder::default();
+        block_on(display.blank()).unwrap();
+        assert_eq!(display.display.iface.commands, [0x15, 0, 127, 0x75, 0, 63]);
+        assert_eq!(display.display.iface.data, [0u8; RAM_COLUMNS * RAM_ROWS]);
+
+        display.display.iface = Recorder::default();
+        block_on(display.start_horizontal_scroll(ScrollDirection::Left, (0, 8), (128, 16), ScrollInterval::Frames2))
+            .unwrap();
+        display.set_pixel(200, 40, 0xF);
+        assert!(matches!(block_on(display.flush()), Err(Error::Busy)));
+        block_on(display.stop_horizontal_scroll()).unwrap();
+        assert_eq!(
+            display.display.iface.commands,
+            [0x27, 0x00, 8, 0b100, 15, 0, 127, 0x00, 0x2F, 0x2E]
+        );
+    }
+}
@@ -1,5 +1,11 @@
 
-use display_interface::{DataFormat::U8, DisplayError, WriteOnlyDataCommand};
+use display_interface::{DataFormat::U8, WriteOnlyDataCommand};
+#[cfg(feature = "async")]
+use display_interface::AsyncWriteOnlyDataCommand;
+
+use core::ops::RangeInclusive;
+
+use crate::error::Error;
 
 /// SSD1362 Commands
 /// Based on the command table from the OEL9M1020-O-E datasheet
@@ -7,7 +13,7 @@ use display_interface::{DataFormat::U8, DisplayError, WriteOnlyDataCommand};
 
 
 /// Commands
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 #[allow(dead_code)]
 pub enum Command {
     /// Setup column start and end address
@@ -26,8 +32,7 @@ pub enum Command {
 
     /// Set Re-map
     /// Column Address Re-map, Nibble Re-map, Horizontal Address, COM Re-map, SEG Split Odd Even, SEG left/right remap
-    // Remap(bool, bool, bool, bool, bool, bool),
-    Remap(u8), // just a single value for now
+    Remap(RemapConfig),
 
     /// Set display start line
     /// Vertical shift by setting the starting address of display RAM from 0 ~ 63
@@ -41,6 +46,14 @@ pub enum Command {
     /// and number of rows of scrolling. (0-64)
     VScrollArea(u8, u8),
 
+    /// Setup continuous horizontal scroll. Only send while scrolling is deactivated.
+    /// Direction, start row, end row (0-63), start column, end column (0-127) and time between steps
+    HScrollSetup(ScrollDirection, u8, u8, u8, u8, ScrollInterval),
+
+    /// Activate or deactivate scrolling.
+    /// Display RAM has to be rewritten after deactivating
+    ScrollActive(bool),
+
     /// MODE
     Mode(DisplayMode),
 
@@ -67,8 +80,10 @@ pub enum Command {
      /// Set second precharge period. each value is from 1-15
     PreChargePeriod(u8),
 
-    /// GrayScale - configure 16 levels
-    // GrayScale()
+    /// Set gray scale table.
+    /// Pulse widths of gray levels GS1 - GS15, GS0 is always off.
+    /// Use `GrayscaleTable` to create a valid table.
+    GrayScale([u8; 15]),
 
     /// linear LUT
     DefaultGrayScale(),
@@ -89,63 +104,333 @@ pub enum Command {
     /// If True: Lock OLED driver IC MCU interface from entering command
     CommandLock(bool),
 
-    // FadeLevel(u8),
-
-    // Blink(u8),
+    /// Fade out or blinking mode.
+    /// Time between fade steps is (value + 1) * 8 frames, value from 0-15
+    FadeBlink(FadeMode, u8),
 }
 
 impl Command {
+    /// Column window for horizontal or vertical addressing, 0 - 127 with start <= end
+    pub fn column_address(start: u8, end: u8) -> Result<Self, Error> {
+        let (start, end) = checked_span(start, end, 0..=0x7F)?;
+        Ok(Command::ColumnAddress(start, end))
+    }
+
+    /// Row window for horizontal or vertical addressing, 0 - 63 with start <= end
+    pub fn row_address(start: u8, end: u8) -> Result<Self, Error> {
+        let (start, end) = checked_span(start, end, 0..=0x3F)?;
+        Ok(Command::RowAddress(start, end))
+    }
+
+    /// Display start line, 0 - 63
+    pub fn start_line(line: u8) -> Result<Self, Error> {
+        Ok(Command::StartLine(checked(line, 0..=0x3F)?))
+    }
+
+    /// Vertical offset by COM, 0 - 63
+    pub fn display_offset(offset: u8) -> Result<Self, Error> {
+        Ok(Command::DisplayOffset(checked(offset, 0..=0x3F)?))
+    }
+
+    /// Vertical scroll area: rows above it and rows in it, 64 rows in total at most
+    pub fn vscroll_area(above: u8, lines: u8) -> Result<Self, Error> {
+        let above = checked(above, 0..=0x3F)?;
+        let lines = checked(lines, 0..=0x40 - above)?;
+        Ok(Command::VScrollArea(above, lines))
+    }
+
+    /// Horizontal scroll of rows 0 - 63 and columns 0 - 127, start <= end for both
+    pub fn hscroll_setup(direction: ScrollDirection, row_start: u8, row_end: u8, col_start: u8, col_end: u8, interval: ScrollInterval) -> Result<Self, Error> {
+        let (row_start, row_end) = checked_span(row_start, row_end, 0..=0x3F)?;
+        let (col_start, col_end) = checked_span(col_start, col_end, 0..=0x7F)?;
+        Ok(Command::HScrollSetup(direction, row_start, row_end, col_start, col_end, interval))
+    }
+
+    /// Multiplex ratio (number of COM lines - 1), 3 - 63
+    pub fn multiplex(ratio: u8) -> Result<Self, Error> {
+        Ok(Command::Multiplex(checked(ratio, 3..=0x3F)?))
+    }
+
+    /// Oscillator frequency and divide ratio - 1, 0 - 15 each
+    pub fn display_clock_div(fosc: u8, divide: u8) -> Result<Self, Error> {
+        Ok(Command::DisplayClockDiv(checked(fosc, 0..=0xF)?, checked(divide, 0..=0xF)?))
+    }
+
+    /// Second precharge period, 1 - 15
+    pub fn precharge_period(period: u8) -> Result<Self, Error> {
+        Ok(Command::PreChargePeriod(checked(period, 1..=0xF)?))
+    }
+
+    /// Precharge voltage level, 0 - 0x1F
+    pub fn precharge_voltage(level: u8) -> Result<Self, Error> {
+        Ok(Command::PreChargeVoltage(checked(level, 0..=0x1F)?))
+    }
+
+    /// Fade out or blinking mode with an interval of 0 - 15
+    pub fn fade_blink(mode: FadeMode, interval: u8) -> Result<Self, Error> {
+        Ok(Command::FadeBlink(mode, checked(interval, 0..=0xF)?))
+    }
+
     /// Send command to SSD1362
-    pub fn send<DI>(self, iface: &mut DI) -> Result<(), DisplayError>
+    pub fn send<DI>(self, iface: &mut DI) -> Result<(), Error>
     where
         DI: WriteOnlyDataCommand,
     {
+        let (data, len) = self.encode();
+        iface.send_commands(U8(&data[0..len]))?;
+        Ok(())
+    }
 
-        // Transform command into a fixed size array of 7 u8 and the real length for sending
-        let (data, len) = match self {
-            Command::ColumnAddress(start, end) => ([0x15, start, end, 0, 0, 0, 0], 3),
-            Command::RowAddress(start, end) => ([0x75, start, end, 0, 0, 0, 0], 3),
-            Command::Contrast(val) => ([0x81, val, 0, 0, 0, 0, 0], 2),
-            Command::Remap(remap) => ([0xA0, remap, 0, 0, 0, 0, 0], 2),
-            Command::StartLine(line) => ([0xA1, line, 0, 0, 0, 0, 0], 2),
-            Command::DisplayOffset(offset) => ([0xA2, offset, 0, 0, 0, 0, 0], 2),
-            Command::VScrollArea(above, lines) => ([0xA3, above, lines, 0, 0, 0, 0], 3),
-            Command::Mode(mode) => ([mode as u8, 0, 0, 0, 0, 0, 0], 1),
-            Command::Multiplex(ratio) => ([0xA8, ratio, 0, 0, 0, 0, 0], 2),
-            Command::InternalVDD(en) => ([0xAB, en as u8, 0, 0, 0, 0, 0], 2),
-            Command::InternalIREF(en) => ([0xAD, (en as u8) << 4 | 0x8E, 0, 0, 0, 0, 0], 2),
-            Command::DisplayOn(on) => ([0xAE | (on as u8), 0, 0, 0, 0, 0, 0], 1),
-            Command::PhaseLength(len) => ([0xB1, len, 0, 0, 0, 0, 0], 2),
+    /// Send command to SSD1362 without blocking during the transfer
+    #[cfg(feature = "async")]
+    pub async fn send_async<DI>(self, iface: &mut DI) -> Result<(), Error>
+    where
+        DI: AsyncWriteOnlyDataCommand,
+    {
+        let (data, len) = self.encode();
+        iface.send_commands(U8(&data[0..len])).await?;
+        Ok(())
+    }
+
+    /// Transform command into a fixed size array and the real length for sending
+    fn encode(self) -> ([u8; MAX_COMMAND_LEN], usize) {
+        match self {
+            Command::ColumnAddress(start, end) => bytes(&[0x15, start, end]),
+            Command::RowAddress(start, end) => bytes(&[0x75, start, end]),
+            Command::Contrast(val) => bytes(&[0x81, val]),
+            Command::Remap(remap) => bytes(&[0xA0, remap.to_byte()]),
+            Command::StartLine(line) => bytes(&[0xA1, line]),
+            Command::DisplayOffset(offset) => bytes(&[0xA2, offset]),
+            Command::VScrollArea(above, lines) => bytes(&[0xA3, above, lines]),
+            Command::HScrollSetup(direction, row_start, row_end, col_start, col_end, interval) => {
+                bytes(&[direction as u8, 0, row_start, interval as u8, row_end, col_start, col_end, 0])
+            },
+            Command::ScrollActive(active) => bytes(&[0x2E | (active as u8)]),
+            Command::FadeBlink(mode, interval) => bytes(&[0x23, (mode as u8) << 4 | (interval & 0xF)]),
+            Command::Mode(mode) => bytes(&[mode as u8]),
+            Command::Multiplex(ratio) => bytes(&[0xA8, ratio]),
+            Command::InternalVDD(en) => bytes(&[0xAB, en as u8]),
+            Command::InternalIREF(en) => bytes(&[0xAD, (en as u8) << 4 | 0x8E]),
+            Command::DisplayOn(on) => bytes(&[0xAE | (on as u8)]),
+            Command::PhaseLength(len) => bytes(&[0xB1, len]),
             Command::DisplayClockDiv(fosc, div) => {
-                ([0xB3, ((0xF & fosc) << 4) | (0xF & div), 0, 0, 0, 0, 0], 2)
+                bytes(&[0xB3, ((0xF & fosc) << 4) | (0xF & div)])
             },
-            Command::PreChargePeriod(period) => ([0xB6, period, 0, 0, 0, 0, 0], 2),
-            Command::DefaultGrayScale()=> ([0xB9, 0, 0, 0, 0, 0, 0], 2),
-            Command::PreChargeVoltage(vol) => ([0xBC, vol, 0, 0, 0, 0, 0], 2),
-            Command::PreChargeCapacitor(cap) => ([0xBD, cap as u8, 0, 0, 0, 0, 0], 2),
-            Command::VcomhDeselect(level) => ([0xBE, (level as u8), 0, 0, 0, 0, 0], 2),
-            Command::CommandLock(lock) => ([0xFD, ((lock as u8) & 0x1 << 2) | 0x12, 0, 0, 0, 0, 0], 2),
-        };
+            Command::PreChargePeriod(period) => bytes(&[0xB6, period]),
+            Command::GrayScale(table) => {
+                let (mut data, _) = bytes(&[0xB8]);
+                data[1..16].copy_from_slice(&table);
+                (data, 16)
+            },
+            Command::DefaultGrayScale()=> bytes(&[0xB9]),
+            Command::PreChargeVoltage(vol) => bytes(&[0xBC, vol]),
+            Command::PreChargeCapacitor(cap) => bytes(&[0xBD, cap as u8]),
+            Command::VcomhDeselect(level) => bytes(&[0xBE, (level as u8)]),
+            Command::CommandLock(lock) => bytes(&[0xFD, ((lock as u8) << 2) | 0x12]),
+        }
+    }
+}
 
-        // Send command over the interface
-        iface.send_commands(U8(&data[0..len]))
+/// Longest command: gray scale table command byte followed by 15 pulse widths
+const MAX_COMMAND_LEN: usize = 16;
+
+/// Check that a command argument is within the range from the datasheet
+fn checked(value: u8, range: RangeInclusive<u8>) -> Result<u8, Error> {
+    if range.contains(&value) {
+        Ok(value)
+    } else {
+        Err(Error::InvalidArgument)
     }
 }
 
+/// Check a start and end address, both in range and the start not after the end
+fn checked_span(start: u8, end: u8, range: RangeInclusive<u8>) -> Result<(u8, u8), Error> {
+    if start > end {
+        return Err(Error::InvalidArgument);
+    }
+    Ok((checked(start, range.clone())?, checked(end, range)?))
+}
 
+/// Copy command bytes into a fixed size array, returns the array and the number of bytes used
+fn bytes(command: &[u8]) -> ([u8; MAX_COMMAND_LEN], usize) {
+    let mut data = [0; MAX_COMMAND_LEN];
+    data[..command.len()].copy_from_slice(command);
+    (data, command.len())
+}
 
-/// Vcomh Deselect level
-#[derive(Debug, Clone, Copy)]
+/// Room for the complete register state, see `Display::reapply_state`
+const COMMAND_BUFFER_LEN: usize = 96;
+
+/// Collects several commands to send them with a single `send_commands` call,
+/// so chip select and D/C are only toggled once for the whole batch.
+///
+/// ```ignore
+/// let mut commands = CommandBuffer::new();
+/// commands.push(Command::ColumnAddress(0, 127))?;
+/// commands.push(Command::RowAddress(0, 63))?;
+/// commands.send(&mut interface)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct CommandBuffer {
+    data: [u8; COMMAND_BUFFER_LEN],
+    len: usize,
+}
+
+impl CommandBuffer {
+    /// Create an empty buffer
+    pub fn new() -> Self {
+        CommandBuffer { data: [0; COMMAND_BUFFER_LEN], len: 0 }
+    }
+
+    /// Append a command. Fails with `Error::BufferOverflow` when it doesn't fit,
+    /// the buffer is left unchanged in that case.
+    pub fn push(&mut self, command: Command) -> Result<(), Error> {
+        let (data, len) = command.encode();
+        let end = self.len + len;
+        if end > COMMAND_BUFFER_LEN {
+            return Err(Error::BufferOverflow);
+        }
+
+        self.data[self.len..end].copy_from_slice(&data[..len]);
+        self.len = end;
+        Ok(())
+    }
+
+    /// Append several commands, fails like `push`
+    pub fn extend<I: IntoIterator<Item = Command>>(&mut self, commands: I) -> Result<(), Error> {
+        commands.into_iter().try_for_each(|command| self.push(command))
+    }
+
+    /// The encoded commands collected so far
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data[..self.len]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Remove all commands
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Send all commands in a single transaction. Nothing is sent when the buffer is empty.
+    pub fn send<DI>(&self, iface: &mut DI) -> Result<(), Error>
+    where
+        DI: WriteOnlyDataCommand,
+    {
+        if !self.is_empty() {
+            iface.send_commands(U8(self.as_bytes()))?;
+        }
+        Ok(())
+    }
+
+    /// Send all commands in a single transaction without blocking during the transfer
+    #[cfg(feature = "async")]
+    pub async fn send_async<DI>(&self, iface: &mut DI) -> Result<(), Error>
+    where
+        DI: AsyncWriteOnlyDataCommand,
+    {
+        if !self.is_empty() {
+            iface.send_commands(U8(self.as_bytes())).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for CommandBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+
+/// Re-map and addressing mode settings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RemapConfig {
+    /// Reverse the column address order
+    pub column_remap: bool,
+    /// Swap the two pixels in every byte
+    pub nibble_remap: bool,
+    /// Vertical address increment instead of horizontal
+    pub vertical_addressing: bool,
+    /// Scan COM lines in reverse order
+    pub com_remap: bool,
+    /// Split odd and even SEG lines
+    pub seg_split: bool,
+    /// Swap left and right SEG lines
+    pub seg_left_right: bool,
+}
+
+impl RemapConfig {
+    /// Encode into the parameter byte of the re-map command
+    pub fn to_byte(&self) -> u8 {
+        (self.column_remap as u8)
+            | (self.nibble_remap as u8) << 1
+            | (self.vertical_addressing as u8) << 2
+            | (self.com_remap as u8) << 4
+            | (self.seg_split as u8) << 6
+            | (self.seg_left_right as u8) << 7
+    }
+
+    /// Decode the parameter byte of the re-map command, reserved bits are ignored
+    pub fn from_byte(byte: u8) -> Self {
+        RemapConfig {
+            column_remap: byte & 1 != 0,
+            nibble_remap: byte & (1 << 1) != 0,
+            vertical_addressing: byte & (1 << 2) != 0,
+            com_remap: byte & (1 << 4) != 0,
+            seg_split: byte & (1 << 6) != 0,
+            seg_left_right: byte & (1 << 7) != 0,
+        }
+    }
+}
+
+/// Horizontal scroll direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollDirection {
+    Right = 0x26,
+    Left = 0x27,
+}
+
+/// Time between horizontal scroll steps, in frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollInterval {
+    Frames2 = 0b100,
+    Frames3 = 0b101,
+    Frames4 = 0b110,
+    Frames5 = 0b111,
+    Frames6 = 0b000,
+    Frames10 = 0b001,
+    Frames100 = 0b010,
+    Frames200 = 0b011,
+}
+
+/// Hardware fade out and blinking mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FadeMode {
+    /// Normal display
+    Disabled = 0b00,
+    /// Fade out to off, and stay off
+    FadeOut = 0b10,
+    /// Fade out and in continuously
+    Blink = 0b11,
+}
+
+/// Display mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(dead_code)]
 pub enum DisplayMode {
-    Normal = 0x4,
-    AllOn = 0x5,
-    AllOff = 0x6,
-    Inverse = 0x7
+    Normal = 0xA4,
+    AllOn = 0xA5,
+    AllOff = 0xA6,
+    Inverse = 0xA7
 }
 
 /// Vcomh Deselect level
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(dead_code)]
 pub enum VcomhLevel {
     /// 0.72 * Vcc
@@ -154,4 +439,158 @@ pub enum VcomhLevel {
     V082 = 0b101,
     /// 0.83 * Vcc
     V086 = 0b111
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_collects_commands_in_a_buffer() {
+        let mut commands = CommandBuffer::new();
+        assert!(commands.is_empty());
+
+        commands.push(Command::ColumnAddress(0, 0x7F)).unwrap();
+        commands.push(Command::DisplayOn(true)).unwrap();
+        assert_eq!(commands.as_bytes(), [0x15, 0x00, 0x7F, 0xAF]);
+
+        // a command that doesn't fit is not added partially
+        while commands.push(Command::GrayScale([0; 15])).is_ok() {}
+        let len = commands.as_bytes().len();
+        assert!(matches!(commands.push(Command::GrayScale([0; 15])), Err(Error::BufferOverflow)));
+        assert_eq!(commands.as_bytes().len(), len);
+
+        commands.clear();
+        assert!(commands.is_empty());
+    }
+
+    #[test]
+    fn it_encodes_the_remap_byte() {
+        let rotate0 = RemapConfig {
+            vertical_addressing: true,
+            com_remap: true,
+            seg_split: true,
+            ..RemapConfig::default()
+        };
+        assert_eq!(rotate0.to_byte(), 0x54);
+
+        let rotate180 = RemapConfig {
+            column_remap: true,
+            vertical_addressing: true,
+            seg_split: true,
+            ..RemapConfig::default()
+        };
+        assert_eq!(rotate180.to_byte(), 0x45);
+
+        let all = RemapConfig {
+            column_remap: true,
+            nibble_remap: true,
+            vertical_addressing: true,
+            com_remap: true,
+            seg_split: true,
+            seg_left_right: true,
+        };
+        assert_eq!(all.to_byte(), 0xD7);
+
+        for byte in &[0x00, 0x54, 0x45, 0xD7] {
+            assert_eq!(RemapConfig::from_byte(*byte).to_byte(), *byte);
+        }
+    }
+
+    #[test]
+    fn it_encodes_every_command() {
+        let remap = RemapConfig::from_byte(0x54);
+        let table = [1, 2, 3, 4, 7, 9, 13, 17, 21, 26, 32, 39, 46, 54, 63];
+
+        let cases: &[(Command, &[u8])] = &[
+            (Command::ColumnAddress(0x10, 0x7F), &[0x15, 0x10, 0x7F]),
+            (Command::RowAddress(0x00, 0x3F), &[0x75, 0x00, 0x3F]),
+            (Command::Contrast(0x7F), &[0x81, 0x7F]),
+            (Command::Remap(remap), &[0xA0, 0x54]),
+            (Command::StartLine(0x20), &[0xA1, 0x20]),
+            (Command::DisplayOffset(0x10), &[0xA2, 0x10]),
+            (Command::VScrollArea(0x08, 0x20), &[0xA3, 0x08, 0x20]),
+            (
+                Command::HScrollSetup(ScrollDirection::Right, 0, 63, 2, 100, ScrollInterval::Frames200),
+                &[0x26, 0x00, 0, 0b011, 63, 2, 100, 0x00],
+            ),
+            (Command::ScrollActive(false), &[0x2E]),
+            (Command::ScrollActive(true), &[0x2F]),
+            (Command::Mode(DisplayMode::Normal), &[0xA4]),
+            (Command::Mode(DisplayMode::AllOn), &[0xA5]),
+            (Command::Mode(DisplayMode::AllOff), &[0xA6]),
+            (Command::Mode(DisplayMode::Inverse), &[0xA7]),
+            (Command::Multiplex(0x3F), &[0xA8, 0x3F]),
+            (Command::InternalVDD(false), &[0xAB, 0x00]),
+            (Command::InternalVDD(true), &[0xAB, 0x01]),
+            (Command::InternalIREF(false), &[0xAD, 0x8E]),
+            (Command::InternalIREF(true), &[0xAD, 0x9E]),
+            (Command::DisplayOn(false), &[0xAE]),
+            (Command::DisplayOn(true), &[0xAF]),
+            (Command::PhaseLength(0x22), &[0xB1, 0x22]),
+            (Command::DisplayClockDiv(0xF, 0x1), &[0xB3, 0xF1]),
+            (Command::PreChargePeriod(0x08), &[0xB6, 0x08]),
+            (
+                Command::GrayScale(table),
+                &[0xB8, 1, 2, 3, 4, 7, 9, 13, 17, 21, 26, 32, 39, 46, 54, 63],
+            ),
+            (Command::DefaultGrayScale(), &[0xB9]),
+            (Command::PreChargeVoltage(0x1F), &[0xBC, 0x1F]),
+            (Command::PreChargeCapacitor(false), &[0xBD, 0x00]),
+            (Command::PreChargeCapacitor(true), &[0xBD, 0x01]),
+            (Command::VcomhDeselect(VcomhLevel::V072), &[0xBE, 0x00]),
+            (Command::VcomhDeselect(VcomhLevel::V082), &[0xBE, 0x05]),
+            (Command::VcomhDeselect(VcomhLevel::V086), &[0xBE, 0x07]),
+            (Command::CommandLock(false), &[0xFD, 0x12]),
+            (Command::CommandLock(true), &[0xFD, 0x16]),
+            (Command::FadeBlink(FadeMode::Disabled, 0), &[0x23, 0x00]),
+            (Command::FadeBlink(FadeMode::FadeOut, 0xF), &[0x23, 0x2F]),
+            (Command::FadeBlink(FadeMode::Blink, 0x3), &[0x23, 0x33]),
+        ];
+
+        for (command, expected) in cases {
+            let (data, len) = command.encode();
+            assert_eq!(&data[..len], *expected, "{:?}", command);
+        }
+    }
+
+    #[test]
+    fn it_checks_argument_ranges() {
+        let valid = [
+            Command::column_address(0, 127),
+            Command::row_address(5, 5),
+            Command::start_line(63),
+            Command::display_offset(0),
+            Command::vscroll_area(0, 64),
+            Command::vscroll_area(63, 1),
+            Command::hscroll_setup(ScrollDirection::Left, 0, 63, 0, 127, ScrollInterval::Frames2),
+            Command::multiplex(3),
+            Command::display_clock_div(0xF, 0xF),
+            Command::precharge_period(1),
+            Command::precharge_voltage(0x1F),
+            Command::fade_blink(FadeMode::Blink, 0xF),
+        ];
+        assert!(valid.iter().all(|c| c.is_ok()));
+
+        let invalid = [
+            Command::column_address(0, 128),
+            Command::column_address(10, 9),
+            Command::row_address(0, 64),
+            Command::start_line(64),
+            Command::display_offset(64),
+            Command::vscroll_area(64, 0),
+            Command::vscroll_area(1, 64),
+            Command::hscroll_setup(ScrollDirection::Left, 8, 7, 0, 127, ScrollInterval::Frames2),
+            Command::hscroll_setup(ScrollDirection::Left, 0, 63, 0, 128, ScrollInterval::Frames2),
+            Command::multiplex(2),
+            Command::multiplex(64),
+            Command::display_clock_div(0x10, 0),
+            Command::display_clock_div(0, 0x10),
+            Command::precharge_period(0),
+            Command::precharge_period(16),
+            Command::precharge_voltage(0x20),
+            Command::fade_blink(FadeMode::FadeOut, 16),
+        ];
+        assert!(invalid.iter().all(|c| matches!(c, Err(Error::InvalidArgument))));
+    }
+}
@@ -0,0 +1,273 @@
+//! Adapters for embedded-hal 0.2 and display-interface 0.4
+//!
+//! The driver uses the embedded-hal 1.0 and display-interface 0.5 traits. HALs and interface
+//! crates that are still on the old traits can be wrapped in `Compat`:
+//!
+//! ```ignore
+//! let interface = Compat(display_interface_spi::SPIInterface::new(spi, dc, cs));
+//! let mut display = Display::new(interface, DisplayRotation::Rotate0);
+//! let mut power = PowerControl::new(Compat(rst), Compat(vcc_en));
+//! power.power_on(&mut display, &mut Compat(delay))?;
+//! ```
+//!
+//! A wrapped embedded-hal 0.2 SPI peripheral is an embedded-hal 1.0 `SpiBus`, so it also works
+//! with `SpiDevice` implementations like the ones from embedded-hal-bus and display-interface-spi 0.5:
+//!
+//! ```ignore
+//! let device = ExclusiveDevice::new_no_delay(Compat(spi), Compat(cs))?;
+//! let interface = display_interface_spi::SPIInterface::new(device, Compat(dc));
+//! ```
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use display_interface_04 as di04;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{self, ErrorKind, ErrorType, OutputPin};
+use embedded_hal::spi::{self, SpiBus};
+use embedded_hal_02 as hal02;
+
+/// Wraps an embedded-hal 0.2 pin or delay, or a display-interface 0.4 interface,
+/// and implements the current version of its trait
+#[derive(Debug)]
+pub struct Compat<T>(pub T);
+
+impl<T> Compat<T> {
+    /// Give back the wrapped pin, delay or interface
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// Error of an embedded-hal 0.2 pin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PinError<E>(pub E);
+
+impl<E: core::fmt::Debug> digital::Error for PinError<E> {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+impl<P> ErrorType for Compat<P>
+where
+    P: hal02::digital::v2::OutputPin,
+    P::Error: core::fmt::Debug,
+{
+    type Error = PinError<P::Error>;
+}
+
+impl<P> OutputPin for Compat<P>
+where
+    P: hal02::digital::v2::OutputPin,
+    P::Error: core::fmt::Debug,
+{
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.set_low().map_err(PinError)
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.set_high().map_err(PinError)
+    }
+}
+
+/// Error of an embedded-hal 0.2 SPI peripheral
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpiError<E>(pub E);
+
+impl<E: core::fmt::Debug> spi::Error for SpiError<E> {
+    fn kind(&self) -> spi::ErrorKind {
+        spi::ErrorKind::Other
+    }
+}
+
+impl<S, E> spi::ErrorType for Compat<S>
+where
+    S: hal02::blocking::spi::Write<u8, Error = E> + hal02::blocking::spi::Transfer<u8, Error = E>,
+    E: core::fmt::Debug,
+{
+    type Error = SpiError<E>;
+}
+
+impl<S, E> SpiBus for Compat<S>
+where
+    S: hal02::blocking::spi::Write<u8, Error = E> + hal02::blocking::spi::Transfer<u8, Error = E>,
+    E: core::fmt::Debug,
+{
+    fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        words.fill(0);
+        self.transfer_in_place(words)
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.0.write(words).map_err(SpiError)
+    }
+
+    /// Transfers a byte at a time, the old traits need the same buffer for reading and writing
+    fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+        for i in 0..read.len().max(write.len()) {
+            let mut word = [write.get(i).copied().unwrap_or(0)];
+            self.0.transfer(&mut word).map_err(SpiError)?;
+            if let Some(byte) = read.get_mut(i) {
+                *byte = word[0];
+            }
+        }
+        Ok(())
+    }
+
+    fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        self.0.transfer(words).map(|_| ()).map_err(SpiError)
+    }
+
+    /// The blocking traits of embedded-hal 0.2 return when the transfer is done
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<D> DelayNs for Compat<D>
+where
+    D: hal02::blocking::delay::DelayUs<u32> + hal02::blocking::delay::DelayMs<u32>,
+{
+    /// The old delay traits only go down to microseconds, rounds up
+    fn delay_ns(&mut self, ns: u32) {
+        self.0.delay_us(ns.div_ceil(1000));
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        self.0.delay_us(us);
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        self.0.delay_ms(ms);
+    }
+}
+
+impl<DI> WriteOnlyDataCommand for Compat<DI>
+where
+    DI: di04::WriteOnlyDataCommand,
+{
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        let cmd = data_format(cmd)?;
+        self.0.send_commands(cmd).map_err(display_error)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        let buf = data_format(buf)?;
+        self.0.send_data(buf).map_err(display_error)
+    }
+}
+
+fn data_format(format: DataFormat<'_>) -> Result<di04::DataFormat<'_>, DisplayError> {
+    Ok(match format {
+        DataFormat::U8(data) => di04::DataFormat::U8(data),
+        DataFormat::U16(data) => di04::DataFormat::U16(data),
+        DataFormat::U16BE(data) => di04::DataFormat::U16BE(data),
+        DataFormat::U16LE(data) => di04::DataFormat::U16LE(data),
+        DataFormat::U8Iter(iter) => di04::DataFormat::U8Iter(iter),
+        DataFormat::U16BEIter(iter) => di04::DataFormat::U16BEIter(iter),
+        DataFormat::U16LEIter(iter) => di04::DataFormat::U16LEIter(iter),
+        _ => return Err(DisplayError::DataFormatNotImplemented),
+    })
+}
+
+fn display_error(error: di04::DisplayError) -> DisplayError {
+    match error {
+        di04::DisplayError::InvalidFormatError => DisplayError::InvalidFormatError,
+        di04::DisplayError::BusWriteError => DisplayError::BusWriteError,
+        di04::DisplayError::DCError => DisplayError::DCError,
+        di04::DisplayError::CSError => DisplayError::CSError,
+        di04::DisplayError::DataFormatNotImplemented => DisplayError::DataFormatNotImplemented,
+        di04::DisplayError::RSError => DisplayError::RSError,
+        di04::DisplayError::OutOfBoundsError => DisplayError::OutOfBoundsError,
+        _ => DisplayError::BusWriteError,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::{Display, DisplayRotation};
+    use std::vec::Vec;
+
+    /// display-interface 0.4 interface that records the commands
+    #[derive(Default)]
+    struct OldInterface(Vec<u8>);
+
+    impl di04::WriteOnlyDataCommand for OldInterface {
+        fn send_commands(&mut self, cmd: di04::DataFormat<'_>) -> Result<(), di04::DisplayError> {
+            match cmd {
+                di04::DataFormat::U8(bytes) => self.0.extend_from_slice(bytes),
+                _ => return Err(di04::DisplayError::DataFormatNotImplemented),
+            }
+            Ok(())
+        }
+
+        fn send_data(&mut self, _buf: di04::DataFormat<'_>) -> Result<(), di04::DisplayError> {
+            Err(di04::DisplayError::CSError)
+        }
+    }
+
+    #[test]
+    fn it_drives_old_interfaces() {
+        let mut display = Display::new(Compat(OldInterface::default()), DisplayRotation::Rotate0);
+
+        display.on().unwrap();
+        assert!(matches!(display.draw(&[0]), Err(crate::error::Error::Comm(DisplayError::CSError))));
+        assert_eq!(display.iface.0 .0, [0xAF]);
+    }
+
+    /// embedded-hal 0.2 SPI peripheral and pin that log what happens on the bus
+    struct OldSpi<'a>(&'a core::cell::RefCell<Vec<&'static str>>, Vec<u8>);
+
+    impl hal02::blocking::spi::Write<u8> for OldSpi<'_> {
+        type Error = ();
+
+        fn write(&mut self, words: &[u8]) -> Result<(), ()> {
+            self.0.borrow_mut().push("write");
+            self.1.extend_from_slice(words);
+            Ok(())
+        }
+    }
+
+    impl hal02::blocking::spi::Transfer<u8> for OldSpi<'_> {
+        type Error = ();
+
+        fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], ()> {
+            self.1.extend_from_slice(words);
+            Ok(words)
+        }
+    }
+
+    struct OldPin<'a>(&'static str, &'a core::cell::RefCell<Vec<&'static str>>);
+
+    impl hal02::digital::v2::OutputPin for OldPin<'_> {
+        type Error = ();
+
+        fn set_low(&mut self) -> Result<(), ()> {
+            self.1.borrow_mut().push(self.0);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), ()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn it_drives_old_spi_peripherals_as_spi_device() {
+        use embedded_hal_bus::spi::ExclusiveDevice;
+
+        let log = core::cell::RefCell::new(Vec::new());
+        let device = ExclusiveDevice::new_no_delay(Compat(OldSpi(&log, Vec::new())), Compat(OldPin("cs low", &log))).unwrap();
+        let interface = display_interface_spi::SPIInterface::new(device, Compat(OldPin("dc low", &log)));
+        let mut display = Display::new(interface, DisplayRotation::Rotate0);
+
+        display.on().unwrap();
+        assert_eq!(*log.borrow(), ["dc low", "cs low", "write"]);
+
+        let mut bus = Compat(OldSpi(&log, Vec::new()));
+        let mut read = [0; 3];
+        bus.transfer(&mut read, &[1, 2]).unwrap();
+        assert_eq!(bus.0 .1, [1, 2, 0]);
+        assert_eq!(read, [1, 2, 0]);
+    }
+}
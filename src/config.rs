@@ -0,0 +1,156 @@
+//! Init-time configuration of the display
+//!
+//! Different OLED modules need different driving parameters. `DisplayConfig` holds everything
+//! `Display::init` sends, the defaults work for the OEL9M1020 module.
+//!
+//! ```ignore
+//! let config = DisplayConfig::new()
+//!     .phase_length(0x22)
+//!     .vcomh(VcomhLevel::V072)
+//!     .grayscale(GrayscaleTable::GAMMA_2_2);
+//! let mut display = Display::with_config(interface, DisplayRotation::Rotate0, config);
+//! display.init()?;
+//! ```
+
+use crate::command::{DisplayMode, RemapConfig, VcomhLevel};
+use crate::display::DisplaySize;
+use crate::grayscale::GrayscaleTable;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayConfig {
+    pub(crate) size: DisplaySize,
+    pub(crate) remap: Option<RemapConfig>,
+    pub(crate) internal_vdd: bool,
+    pub(crate) internal_iref: bool,
+    pub(crate) start_line: u8,
+    pub(crate) display_offset: u8,
+    pub(crate) mode: DisplayMode,
+    pub(crate) phase_length: u8,
+    pub(crate) clock_div: (u8, u8),
+    pub(crate) precharge_period: Option<u8>,
+    pub(crate) grayscale: Option<GrayscaleTable>,
+    pub(crate) precharge_voltage: u8,
+    pub(crate) precharge_capacitor: Option<bool>,
+    pub(crate) vcomh: VcomhLevel,
+    pub(crate) contrast: u8,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        DisplayConfig {
+            size: DisplaySize::DISPLAY_256X64,
+            remap: None,
+            internal_vdd: true,
+            internal_iref: true,
+            start_line: 0,
+            display_offset: 0,
+            mode: DisplayMode::Normal,
+            phase_length: 0x11,
+            clock_div: (0xF, 0x0), // as fast as possible
+            precharge_period: None,
+            grayscale: None,
+            precharge_voltage: 0x04,
+            precharge_capacitor: None,
+            vcomh: VcomhLevel::V082,
+            contrast: 0x7F,
+        }
+    }
+}
+
+impl DisplayConfig {
+    /// Configuration with the default values
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Size of the panel, also sets the multiplex ratio (default 256x64)
+    pub fn size(mut self, size: DisplaySize) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Re-map and addressing mode (default: derived from the display rotation).
+    /// Rotation by 90º and 270º is done in software on top of this.
+    pub fn remap(mut self, remap: RemapConfig) -> Self {
+        self.remap = Some(remap);
+        self
+    }
+
+    /// Use the internal VDD regulator (default) or an external VDD
+    pub fn internal_vdd(mut self, internal: bool) -> Self {
+        self.internal_vdd = internal;
+        self
+    }
+
+    /// Use the internal current reference (default) or an external I_REF resistor
+    pub fn internal_iref(mut self, internal: bool) -> Self {
+        self.internal_iref = internal;
+        self
+    }
+
+    /// Display RAM row shown on the first line, 0 - 63 (default 0)
+    pub fn start_line(mut self, line: u8) -> Self {
+        self.start_line = line;
+        self
+    }
+
+    /// Vertical offset by COM, 0 - 63 (default 0)
+    pub fn display_offset(mut self, offset: u8) -> Self {
+        self.display_offset = offset;
+        self
+    }
+
+    /// Display mode after init (default `DisplayMode::Normal`)
+    pub fn mode(mut self, mode: DisplayMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// PWM phase 1 and phase 2 length, one nibble each (default 0x11)
+    pub fn phase_length(mut self, length: u8) -> Self {
+        self.phase_length = length;
+        self
+    }
+
+    /// Oscillator frequency and divide ratio - 1, 0 - 15 each (default 0xF, 0x0)
+    pub fn clock(mut self, fosc: u8, divide: u8) -> Self {
+        self.clock_div = (fosc, divide);
+        self
+    }
+
+    /// Second precharge period, 1 - 15 (default: not sent, reset value of the display)
+    pub fn precharge_period(mut self, period: u8) -> Self {
+        self.precharge_period = Some(period);
+        self
+    }
+
+    /// Gray scale table (default: the linear table of the display)
+    pub fn grayscale(mut self, table: GrayscaleTable) -> Self {
+        self.grayscale = Some(table);
+        self
+    }
+
+    /// Precharge voltage level, 0 - 0x1F (default 0x04)
+    pub fn precharge_voltage(mut self, level: u8) -> Self {
+        self.precharge_voltage = level;
+        self
+    }
+
+    /// With or without external Vp capacitor (default: not sent, reset value of the display)
+    pub fn precharge_capacitor(mut self, external: bool) -> Self {
+        self.precharge_capacitor = Some(external);
+        self
+    }
+
+    /// Vcomh deselect level (default `VcomhLevel::V082`)
+    pub fn vcomh(mut self, level: VcomhLevel) -> Self {
+        self.vcomh = level;
+        self
+    }
+
+    /// Contrast after init (default 0x7F)
+    pub fn contrast(mut self, contrast: u8) -> Self {
+        self.contrast = contrast;
+        self
+    }
+}
@@ -1,17 +1,22 @@
-use crate::command::{Command, VcomhLevel, DisplayMode};
-// use crate::interface::DisplayInterface;
-// use embedded_graphics::{
-//     drawable::Pixel,
-//     DrawTarget,
-//     geometry::Size,
-//     pixelcolor::{BinaryColor}
-// };
-use display_interface::{DataFormat::U8, DisplayError, WriteOnlyDataCommand};
-
-
-///! Display rotation
-/// Note that 90º and 270º rotations are not supported by
-// [`TerminalMode`](../mode/terminal/struct.TerminalMode.html).
+use crate::command::{Command, CommandBuffer, FadeMode, RemapConfig, ScrollDirection, ScrollInterval};
+use crate::config::DisplayConfig;
+use crate::error::Error;
+use crate::framebuffer::{Area, FrameBuffer, Upload, RAM_COLUMNS, RAM_ROWS};
+use crate::grayscale::GrayscaleTable;
+use crate::registers::{ConfigCommands, Registers, ResumeCommands};
+#[cfg(feature = "graphics")]
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Size},
+    pixelcolor::{Gray4, GrayColor},
+    Pixel,
+};
+use display_interface::{DataFormat::U8, WriteOnlyDataCommand};
+
+
+/// Display rotation
+/// 0º and 180º are done by the display controller,
+/// 90º and 270º are done in software when pixels are written to the framebuffer.
 #[derive(Clone, Copy)]
 pub enum DisplayRotation {
     /// No rotation, normal display
@@ -24,21 +29,131 @@ pub enum DisplayRotation {
     Rotate270,
 }
 
-/// Display size enumeration
-#[derive(Clone, Copy)]
-pub enum DisplaySize {
-    /// 256 by 64 pixels
-    Display256x64,
+/// Size of the panel and where it is connected to the display RAM.
+/// The display RAM is 128 columns of two pixels wide and 64 rows high.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DisplaySize {
+    width: u16,
+    height: u8,
+    column_offset: u8,
+    multiplex: u8,
 }
 
-
 impl DisplaySize {
-    /// Get integral dimensions from DisplaySize
+    /// 256 by 64 pixels, using the complete display RAM
+    pub const DISPLAY_256X64: DisplaySize = DisplaySize {
+        width: 256,
+        height: 64,
+        column_offset: 0,
+        multiplex: 64,
+    };
+
+    /// Panel of `width` by `height` pixels, starting at RAM column `column_offset`.
+    /// The width has to be even since a RAM column is two pixels wide, the height is 4 - 64 rows.
+    /// All rows are driven, see `with_multiplex` for panels with fewer COM lines.
+    /// Returns `Error::InvalidArgument` if the panel does not fit in the display RAM.
+    pub fn new(width: usize, height: usize, column_offset: usize) -> Result<Self, Error> {
+        let fits = width > 0
+            && width & 1 == 0
+            && column_offset + width / 2 <= RAM_COLUMNS
+            && (4..=RAM_ROWS).contains(&height);
+
+        if fits {
+            Ok(DisplaySize {
+                width: width as u16,
+                height: height as u8,
+                column_offset: column_offset as u8,
+                multiplex: height as u8,
+            })
+        } else {
+            Err(Error::InvalidArgument)
+        }
+    }
+
+    /// Number of COM lines that are driven, from the height of the panel up to 64.
+    /// Returns `Error::InvalidArgument` when out of range.
+    pub fn with_multiplex(self, lines: usize) -> Result<Self, Error> {
+        if (self.height as usize..=RAM_ROWS).contains(&lines) {
+            Ok(DisplaySize { multiplex: lines as u8, ..self })
+        } else {
+            Err(Error::InvalidArgument)
+        }
+    }
+
+    /// Get integral dimensions in pixels from DisplaySize
     pub fn dimensions(&self) -> (usize, usize) {
-        match *self {
-            // TODO should this be 128 or 256 (since columns are two pixels wide)?
-            DisplaySize::Display256x64 => (256, 64),
+        (self.width as usize, self.height as usize)
+    }
+
+    /// First RAM column of the panel
+    pub fn column_offset(&self) -> u8 {
+        self.column_offset
+    }
+
+    /// Number of COM lines that are driven
+    pub fn multiplex(&self) -> u8 {
+        self.multiplex
+    }
+
+    /// RAM columns (start, exclusive end) of the panel.
+    /// Column remap mirrors the RAM, which moves the panel to the other end of it.
+    pub(crate) fn ram_columns(&self, column_remap: bool) -> (u8, u8) {
+        let columns = (self.width / 2) as u8;
+        if column_remap {
+            let end = RAM_COLUMNS as u8 - self.column_offset;
+            (end - columns, end)
+        } else {
+            (self.column_offset, self.column_offset + columns)
+        }
+    }
+}
+
+impl Default for DisplaySize {
+    fn default() -> Self {
+        DisplaySize::DISPLAY_256X64
+    }
+}
+
+/// Linear contrast fade, yields one contrast value per step.
+/// Drive it from a timer with `Display::fade_tick`, or step it yourself as an iterator.
+#[derive(Debug, Clone, Copy)]
+pub struct ContrastFade {
+    from: u8,
+    to: u8,
+    steps: u16,
+    step: u16,
+}
+
+impl ContrastFade {
+    /// Fade from one contrast value to another in `steps` steps.
+    /// The last step always ends at `to`, zero steps jump there right away.
+    pub fn new(from: u8, to: u8, steps: u16) -> Self {
+        ContrastFade {
+            from,
+            to,
+            steps: steps.max(1),
+            step: 0,
+        }
+    }
+
+    /// True when every step has been taken
+    pub fn is_done(&self) -> bool {
+        self.step >= self.steps
+    }
+}
+
+impl Iterator for ContrastFade {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.is_done() {
+            return None;
         }
+
+        self.step += 1;
+        let from = self.from as i32;
+        let delta = (self.to as i32 - from) * self.step as i32 / self.steps as i32;
+        Some((from + delta) as u8)
     }
 }
 
@@ -63,90 +178,308 @@ impl DisplaySize {
 
 
 pub struct Display<DI> {
-    iface: DI,
+    pub(crate) iface: DI,
     rotation: DisplayRotation,
-    size: DisplaySize,
-    // displaybuffer: [bool; 256*4] //[row0 row1 row2 ... row62] TODO: buffer size depends on display size
+    config: DisplayConfig,
+    pub(crate) framebuffer: FrameBuffer,
+    /// Shadow copy of everything sent to the controller
+    pub(crate) registers: Registers,
+    pub(crate) fade: Option<ContrastFade>,
 }
 
 
-impl<DI> Display<DI>
-where
-    DI: WriteOnlyDataCommand,
-{
+impl<DI> Display<DI> {
     pub fn new(iface: DI, rotation: DisplayRotation) -> Display<DI> {
-        let size = DisplaySize::Display256x64;
+        Self::with_config(iface, rotation, DisplayConfig::default())
+    }
+
+    /// Create a display with custom init parameters, see `DisplayConfig`
+    pub fn with_config(iface: DI, rotation: DisplayRotation, config: DisplayConfig) -> Display<DI> {
+        Self::with_registers(iface, rotation, config, Registers::default())
+    }
 
+    /// Take over a display that is already configured, with the registers from `into_parts`.
+    /// It doesn't need `init` again, the first `flush` uploads the complete framebuffer.
+    pub fn with_registers(iface: DI, rotation: DisplayRotation, config: DisplayConfig, registers: Registers) -> Display<DI> {
         Display {
             iface,
             rotation,
-            size,
+            config,
+            framebuffer: FrameBuffer::new(),
+            registers,
+            fade: None,
         }
     }
 
-    pub fn init(&mut self) -> Result<(), DisplayError> {
+    /// Init parameters of the display
+    pub fn config(&self) -> &DisplayConfig {
+        &self.config
+    }
 
-        Command::InternalVDD(true).send(&mut self.iface)?;
-        Command::InternalIREF(true).send(&mut self.iface)?;
-        Command::ColumnAddress(0, 0x7f).send(&mut self.iface)?;
-        Command::RowAddress(0, 0x3f).send(&mut self.iface)?;
+    /// Re-map settings in use, from the `DisplayConfig` or derived from the rotation
+    pub fn remap(&self) -> RemapConfig {
+        self.config.remap.unwrap_or(match self.rotation {
+            DisplayRotation::Rotate180 => RemapConfig {
+                column_remap: true,
+                vertical_addressing: true,
+                seg_split: true,
+                ..RemapConfig::default()
+            },
+            // 90º and 270º are rotated in software, see `ram_position`
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => RemapConfig {
+                vertical_addressing: true,
+                com_remap: true,
+                seg_split: true,
+                ..RemapConfig::default()
+            },
+        })
+    }
 
-        // let remap = match self.rotation {
-        //     DisplayRotation::Rotate0 => 0x50, // 0xD2 also works
-        //     DisplayRotation::Rotate180 => 0x43, // 0xC1 also works
-        //     //TODO implement 90 and 270 rotations
-        //     DisplayRotation::Rotate90 => 0x00,
-        //     DisplayRotation::Rotate270 => 0x00
-        // };
+    pub fn dimensions(&self) -> (usize, usize) {
+        let (w, h) = self.config.size.dimensions();
 
-        // vertical mode
-        let remap = match self.rotation {
-            DisplayRotation::Rotate0 => 0x54,
-            DisplayRotation::Rotate180 => 0x45, // 0b01[0]0 [0]101
-            DisplayRotation::Rotate90 => 0x00,
-            DisplayRotation::Rotate270 => 0x00
-        };
+        match self.rotation {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (w, h),
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (h, w),
+        }
+    }
 
+    /// Current contrast
+    pub fn contrast(&self) -> u8 {
+        self.registers.contrast()
+    }
 
-        Command::Remap(remap).send(&mut self.iface)?;
+    /// Shadow copy of the controller registers, from the commands sent so far
+    pub fn registers(&self) -> &Registers {
+        &self.registers
+    }
 
-        Command::StartLine(0).send(&mut self.iface)?;
-        Command::DisplayOffset(0).send(&mut self.iface)?;
-        Command::Mode(DisplayMode::Normal).send(&mut self.iface)?;
-        Command::Multiplex(0x3F).send(&mut self.iface)?;
-        Command::PhaseLength(0x11).send(&mut self.iface)?;
-        Command::DisplayClockDiv(0xf, 0x0).send(&mut self.iface)?; // as fast as possible
-        Command::DefaultGrayScale().send(&mut self.iface)?;
-        Command::PreChargeVoltage(0x04).send(&mut self.iface)?;
-        Command::VcomhDeselect(VcomhLevel::V082).send(&mut self.iface)?;
+    /// Start fading from the current contrast to `target` in `ticks` steps.
+    /// Nothing is sent until `fade_tick` is called, typically from a timer of the application.
+    /// For a fade in on boot, set the contrast to 0 before turning the display on.
+    pub fn start_fade(&mut self, target: u8, ticks: u16) {
+        self.fade = Some(ContrastFade::new(self.registers.contrast(), target, ticks));
+    }
 
-        // Command::VScrollArea(20, 30).send(&mut self.iface)?;
+    /// Set a single pixel in the framebuffer to a gray level from 0 (off) to 15 (full brightness).
+    /// Pixels outside of the display are ignored. Nothing is sent to the display until `flush`.
+    pub fn set_pixel(&mut self, x: usize, y: usize, level: u8) {
+        if let Some((x, y)) = self.ram_position(x, y) {
+            self.framebuffer.set_pixel(x, y, level);
+        }
+    }
 
-        Ok(())
+    /// Get the gray level of a single pixel in the framebuffer.
+    /// Returns `None` for pixels outside of the display.
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<u8> {
+        self.ram_position(x, y)
+            .and_then(|(x, y)| self.framebuffer.get_pixel(x, y))
     }
 
-    pub fn blank(&mut self) -> Result<(), DisplayError> {
-        Command::ColumnAddress(0, 127).send(&mut self.iface)?;
-        Command::RowAddress(0, 63).send(&mut self.iface)?;
+    /// Fill the whole framebuffer with a single gray level
+    pub fn clear(&mut self, level: u8) {
+        self.framebuffer.fill(level);
+    }
 
-        self.draw(&[0u8; 128*64])
+    /// Give back the interface, e.g. to use a shared bus for another device.
+    /// The controller keeps its configuration and RAM, a new `Display` for it doesn't need
+    /// `init` again, but uploads the complete framebuffer on its first `flush`.
+    /// Use `into_parts` to keep the shadow registers as well.
+    pub fn release(self) -> DI {
+        self.iface
     }
 
-    pub fn dimensions(&self) -> (usize, usize) {
-        let (w, h) = self.size.dimensions();
+    /// Give back the interface and the shadow registers. Pass the registers to `with_registers`
+    /// to continue with the configured controller, e.g. with `reapply_state` or `refresh_config`.
+    pub fn into_parts(self) -> (DI, Registers) {
+        (self.iface, self.registers)
+    }
 
-        match self.rotation {
-            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (w, h),
-            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (h, w),
+    /// Commands of the init sequence from the `DisplayConfig`.
+    /// Optional parameters that are not configured are `None`.
+    /// Fails with `Error::InvalidArgument` when a parameter is out of range.
+    pub(crate) fn init_commands(&self) -> Result<[Option<Command>; 17], Error> {
+        let config = &self.config;
+        let (col_start, col_end) = self.ram_columns();
+        let (_, height) = config.size.dimensions();
+
+        Ok([
+            Some(Command::InternalVDD(config.internal_vdd)),
+            Some(Command::InternalIREF(config.internal_iref)),
+            Some(Command::ColumnAddress(col_start, col_end - 1)),
+            Some(Command::RowAddress(0, height as u8 - 1)),
+            Some(Command::Remap(self.remap())),
+            Some(Command::start_line(config.start_line)?),
+            Some(Command::display_offset(config.display_offset)?),
+            Some(Command::Mode(config.mode)),
+            Some(Command::multiplex(config.size.multiplex() - 1)?),
+            Some(Command::PhaseLength(config.phase_length)),
+            Some(Command::display_clock_div(config.clock_div.0, config.clock_div.1)?),
+            config.precharge_period.map(Command::precharge_period).transpose()?,
+            Some(match config.grayscale {
+                Some(table) => Command::GrayScale(table.widths()),
+                None => Command::DefaultGrayScale(),
+            }),
+            Some(Command::precharge_voltage(config.precharge_voltage)?),
+            config.precharge_capacitor.map(Command::PreChargeCapacitor),
+            Some(Command::VcomhDeselect(config.vcomh)),
+            Some(Command::Contrast(config.contrast)),
+        ])
+    }
+
+    /// Translate an area relative to the panel, like in `set_draw_area`, to display RAM columns.
+    /// Fails with `Error::InvalidArgument` for empty areas and areas outside of the panel.
+    pub(crate) fn panel_area(&self, start: (u8, u8), end: (u8, u8)) -> Result<Area, Error> {
+        let (offset, ram_end) = self.ram_columns();
+        let (_, height) = self.config.size.dimensions();
+
+        if start.0 >= end.0 || start.1 >= end.1 || end.0 > ram_end - offset || end.1 as usize > height {
+            return Err(Error::InvalidArgument);
         }
+
+        Ok(Area {
+            start: (start.0 + offset, start.1),
+            end: (end.0 + offset, end.1),
+        })
+    }
+
+    /// RAM area of a running horizontal scroll, from the shadow registers. Also known after
+    /// `with_registers`, so a scroll started before `into_parts` can still be stopped.
+    pub(crate) fn scroll_area(&self) -> Option<Area> {
+        let (_, rows, columns, _) = self.registers.hscroll().filter(|_| self.registers.is_scrolling())?;
+        Some(Area {
+            start: (columns.0, rows.0),
+            end: (columns.1 + 1, rows.1 + 1),
+        })
+    }
+
+    /// Clear the framebuffer and mark the whole panel to be uploaded
+    pub(crate) fn blank_framebuffer(&mut self) {
+        let (col_start, col_end) = self.ram_columns();
+        let (_, height) = self.config.size.dimensions();
+
+        self.framebuffer.fill(0);
+        self.framebuffer.mark_dirty(Area {
+            start: (col_start, 0),
+            end: (col_end, height as u8),
+        });
+    }
+
+    /// Command to stop a running horizontal scroll, if any. Marks the scrolled area to be sent
+    /// again, since the display RAM content is undefined after scrolling.
+    pub(crate) fn stop_scroll_command(&mut self) -> Option<Command> {
+        let area = self.scroll_area()?;
+        self.framebuffer.mark_dirty(area);
+        Some(Command::ScrollActive(false))
+    }
+
+    /// Commands to start horizontal scrolling of an area relative to the panel
+    pub(crate) fn start_scroll_commands(&mut self, direction: ScrollDirection, start: (u8, u8), end: (u8, u8), interval: ScrollInterval) -> Result<[Option<Command>; 3], Error> {
+        let area = self.panel_area(start, end)?;
+
+        // setup is only allowed while not scrolling
+        Ok([
+            self.stop_scroll_command(),
+            Some(Command::HScrollSetup(direction, area.start.1, area.end.1 - 1, area.start.0, area.end.0 - 1, interval)),
+            Some(Command::ScrollActive(true)),
+        ])
+    }
+
+    /// Commands of the shadow register state, before and after the display RAM may be written.
+    /// See `Registers::config_commands` and `Registers::resume_commands`.
+    /// Fails with `Error::NotInitialized` when the registers don't hold a configuration yet.
+    pub(crate) fn state_commands(&self, animations: bool) -> Result<(ConfigCommands, ResumeCommands), Error> {
+        if !self.registers.is_initialized() {
+            return Err(Error::NotInitialized);
+        }
+        Ok((self.registers.config_commands(animations), self.registers.resume_commands(animations)))
+    }
+
+    /// Upload of the next area that was modified since the last flush, if any.
+    /// Fails with `Error::Busy` while scrolling, the area stays dirty for a later flush.
+    pub(crate) fn next_upload(&self) -> Result<Option<Upload>, Error> {
+        let upload = self.framebuffer
+            .next_dirty()
+            .map(|area| Upload::new(area, self.remap().vertical_addressing));
+
+        if upload.is_some() && self.registers.is_scrolling() {
+            return Err(Error::Busy);
+        }
+        Ok(upload)
+    }
+
+    /// Contrast of the next step of a running fade, if any. Forgets the fade after its last step.
+    pub(crate) fn next_fade_step(&mut self) -> Option<u8> {
+        let contrast = self.fade.as_mut().and_then(|fade| fade.next());
+
+        if self.fade.is_some_and(|fade| fade.is_done()) {
+            self.fade = None;
+        }
+        contrast
+    }
+
+    /// Translate a pixel position on the (rotated) display to a position in display RAM.
+    /// Returns `None` for positions outside of the display.
+    fn ram_position(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+        let (w, h) = self.dimensions();
+        if x >= w || y >= h {
+            return None;
+        }
+
+        let (panel_w, panel_h) = self.config.size.dimensions();
+        let (x, y) = match self.rotation {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (x, y),
+            DisplayRotation::Rotate90 => (panel_w - 1 - y, x),
+            DisplayRotation::Rotate270 => (y, panel_h - 1 - x),
+        };
+
+        let (offset, _) = self.ram_columns();
+        Some((x + 2 * offset as usize, y))
+    }
+
+    /// RAM columns (start, exclusive end) the panel is connected to
+    fn ram_columns(&self) -> (u8, u8) {
+        self.config.size.ram_columns(self.remap().column_remap)
+    }
+}
+
+impl<DI> Display<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Send the init sequence from the `DisplayConfig` in a single transaction.
+    /// Fails with `Error::InvalidArgument` before sending anything when a parameter is out of range.
+    pub fn init(&mut self) -> Result<(), Error> {
+        let commands = self.init_commands()?;
+        self.send_commands(commands.iter().flatten().copied())?;
+        self.registers.mark_initialized();
+
+        // Command::VScrollArea(20, 30).send(&mut self.iface)?;
+
+        Ok(())
+    }
+
+    /// Turn off every pixel, in the framebuffer and on the display.
+    /// The panel is uploaded even when the framebuffer was blank already, e.g. after `draw`.
+    pub fn blank(&mut self) -> Result<(), Error> {
+        self.blank_framebuffer();
+        self.flush()
     }
 
 
     /// Set the position in the framebuffer of the display limiting where any sent data should be
     /// drawn. This method can be used for changing the affected area on the screen as well
     /// as (re-)setting the start point of the next `draw` call.
-    /// Only works in Horizontal or Vertical addressing mode
-    pub fn set_draw_area(&mut self, start: (u8, u8), end: (u8, u8)) -> Result<(), DisplayError> {
+    /// Columns are two pixels wide and start at the first column of the panel, see `DisplaySize`.
+    /// Only works in Horizontal or Vertical addressing mode.
+    /// Fails with `Error::InvalidArgument` when the area is empty or not on the panel.
+    pub fn set_draw_area(&mut self, start: (u8, u8), end: (u8, u8)) -> Result<(), Error> {
+        let area = self.panel_area(start, end)?;
+        self.set_ram_area(area)
+    }
+
+    /// Same as `set_draw_area`, in absolute display RAM columns
+    fn set_ram_area(&mut self, area: Area) -> Result<(), Error> {
 
         // match self.addr_mode {
         //     AddrMode::Page => panic!("Device cannot be in Page mode to set draw area"),
@@ -157,77 +490,631 @@ where
         //     }
         // }
 
-        Command::ColumnAddress(start.0, end.0 - 1).send(&mut self.iface)?;
-        Command::RowAddress(start.1.into(), (end.1 - 1).into()).send(&mut self.iface)?;
-        Ok(())
+        self.send_commands(ram_window(area))
     }
 
 
     /// Send the data to the display for drawing at the current position in the framebuffer
     /// and advance the position accordingly. Cf. `set_draw_area` to modify the area affected by
     /// this method in horizontal / vertical mode.
-    pub fn draw(&mut self, buffer: &[u8]) -> Result<(), DisplayError> {
-        self.iface.send_data(U8(buffer))
+    pub fn draw(&mut self, buffer: &[u8]) -> Result<(), Error> {
+        self.iface.send_data(U8(buffer))?;
+        Ok(())
     }
 
     /// Turn the display on.
-    pub fn on(&mut self) -> Result<(), DisplayError> {
-        Command::DisplayOn(true).send(&mut self.iface)
+    pub fn on(&mut self) -> Result<(), Error> {
+        self.send_command(Command::DisplayOn(true))
     }
 
     /// Turn the display off.
-    pub fn off(&mut self) -> Result<(), DisplayError> {
-        Command::DisplayOn(false).send(&mut self.iface)
+    pub fn off(&mut self) -> Result<(), Error> {
+        self.send_command(Command::DisplayOn(false))
+    }
+
+    /// Move the image up by `offset` rows, 0 - 63
+    pub fn scroll(&mut self, offset: u8) -> Result<(), Error> {
+        self.send_command(Command::display_offset(offset)?)
+    }
+
+    /// Start continuous horizontal scrolling of an area, done by the display without any CPU involvement.
+    /// Start and end are columns (of two pixels) and rows like in `set_draw_area`, the end is exclusive.
+    /// Writing to the display RAM while scrolling corrupts the image, so `flush` fails with
+    /// `Error::Busy` until the scroll is stopped.
+    pub fn start_horizontal_scroll(&mut self, direction: ScrollDirection, start: (u8, u8), end: (u8, u8), interval: ScrollInterval) -> Result<(), Error> {
+        let commands = self.start_scroll_commands(direction, start, end, interval)?;
+        self.send_commands(commands.iter().flatten().copied())
+    }
+
+    /// Stop horizontal scrolling. The scrolled area is sent again on the next `flush`,
+    /// since the display RAM content is undefined after scrolling.
+    pub fn stop_horizontal_scroll(&mut self) -> Result<(), Error> {
+        match self.stop_scroll_command() {
+            Some(command) => self.send_command(command),
+            None => Ok(()),
+        }
+    }
+
+    /// Let the display fade out or blink by itself, e.g. for an alarm state or while the MCU sleeps.
+    /// The time between fade steps is (interval + 1) * 8 frames, interval from 0 to 15.
+    /// `FadeMode::Disabled` returns to the normal display.
+    pub fn set_fade_mode(&mut self, mode: FadeMode, interval: u8) -> Result<(), Error> {
+        self.send_command(Command::fade_blink(mode, interval)?)
+    }
+
+    /// Set the contrast, higher is brighter. Stops a running fade.
+    pub fn set_contrast(&mut self, contrast: u8) -> Result<(), Error> {
+        self.fade = None;
+        self.send_contrast(contrast)
+    }
+
+    /// Set the brightness in percent (0 - 100) of the maximum contrast. Stops a running fade.
+    pub fn set_brightness(&mut self, percent: u8) -> Result<(), Error> {
+        self.set_contrast(brightness_to_contrast(percent))
+    }
+
+    /// Take one step of a running fade.
+    /// Returns true while the fade has steps left, false when it is done or no fade is running.
+    pub fn fade_tick(&mut self) -> Result<bool, Error> {
+        if let Some(contrast) = self.next_fade_step() {
+            self.send_contrast(contrast)?;
+        }
+        Ok(self.fade.is_some())
     }
 
-    pub fn scroll(&mut self, offset: u8) -> Result<(), DisplayError> {
-        Command::DisplayOffset(offset).send(&mut self.iface)
+    fn send_contrast(&mut self, contrast: u8) -> Result<(), Error> {
+        self.send_command(Command::Contrast(contrast))
     }
 
-    // pub fn flush(&mut self) -> Result<(), DisplayError> {
+    /// Set the pulse widths of the gray levels. See `GrayscaleTable` for presets.
+    pub fn set_grayscale_table(&mut self, table: &GrayscaleTable) -> Result<(), Error> {
+        self.send_command(Command::GrayScale(table.widths()))
+    }
+
+    /// Go back to the default linear gray scale table of the display
+    pub fn set_default_grayscale(&mut self) -> Result<(), Error> {
+        self.send_command(Command::DefaultGrayScale())
+    }
 
-    //     let (w, h) = self.dimensions();
+    /// Send a single command and record it in the shadow registers
+    pub fn send_command(&mut self, command: Command) -> Result<(), Error> {
+        command.send(&mut self.iface)?;
+        self.registers.record(command);
+        Ok(())
+    }
 
-    //     for i in 0..h {
+    /// Send several commands in a single transaction and record them in the shadow registers.
+    /// Fails with `Error::BufferOverflow` when they don't fit into a `CommandBuffer`.
+    pub fn send_commands<I>(&mut self, commands: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = Command>,
+        I::IntoIter: Clone,
+    {
+        let commands = commands.into_iter();
+        let mut buffer = CommandBuffer::new();
+        buffer.extend(commands.clone())?;
+        buffer.send(&mut self.iface)?;
 
-    //         let mut linebuffer: [u8; 256/2] = [0; 128];
+        commands.for_each(|command| self.registers.record(command));
+        Ok(())
+    }
+
+    /// Send the complete shadow register state again in a single transaction, to recover
+    /// from an ESD glitch or brown-out that corrupted the controller registers.
+    /// The display RAM is not touched.
+    /// Fails with `Error::NotInitialized` before `init`, or on a display created without the
+    /// registers of a previous `into_parts`.
+    pub fn reapply_state(&mut self) -> Result<(), Error> {
+        let (config, resume) = self.state_commands(true)?;
+        self.send_commands(config.iter().chain(resume.iter()).flatten().copied())
+    }
 
-    //         for j in 0..w {
-    //             let idx: usize = i*w+j;
-    //             let b = self.displaybuffer[idx];
+    /// Watchdog for noisy environments, call it periodically after `init`.
+    /// Sends the shadow register state again and re-uploads the complete framebuffer, so a
+    /// controller that reset or corrupted its registers or RAM shows the right image again.
+    /// The display is only turned on after the upload, and a display that is on gets the same
+    /// RAM content again, so nothing flickers.
+    /// A running horizontal scroll is stopped for the upload and started again from the uploaded
+    /// image. A running fade is not restarted, use `reapply_state` for that.
+    pub fn refresh_config(&mut self) -> Result<(), Error> {
+        let (config, resume) = self.state_commands(false)?;
+        self.send_commands(config.iter().flatten().copied())?;
 
-    //             let line_idx: usize = j/2;
-    //             let shift = j % 2;
-    //             linebuffer[line_idx] |= (0xFF * b as u8) << (4*shift);
-    //         }
-    //         Command::ColumnAddress(0, 127).send(&mut self.iface)?;
-    //         Command::RowAddress(i as u8, 63).send(&mut self.iface)?;
-    //         self.draw(&linebuffer)?;
-    //     }
+        self.framebuffer.mark_dirty(Area::FULL);
+        self.flush()?;
 
-    //     Ok(())
-    // }
+        self.send_commands(resume.iter().flatten().copied())
+    }
+
+    /// Upload the parts of the framebuffer that were modified since the last flush.
+    /// Every modified area is sent in its own column/row window, so small changes
+    /// only cost a few bytes on the bus.
+    /// Fails with `Error::Busy` while a horizontal scroll is running, since writing to the
+    /// display RAM then corrupts the image. The modified areas are kept until the next flush.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        while let Some(mut upload) = self.next_upload()? {
+            self.set_ram_area(upload.area())?;
+            while let Some(data) = upload.next_chunk(&self.framebuffer) {
+                self.iface.send_data(U8(data))?;
+            }
+            self.framebuffer.clean(upload.area());
+        }
+        Ok(())
+    }
 }
 
-// impl<DI> DrawTarget<BinaryColor> for Display<DI>
-// where
-//     DI: WriteOnlyDataCommand,
-// {
-//     type Error = core::convert::Infallible;
+/// Column and row window of a RAM area
+pub(crate) fn ram_window(area: Area) -> [Command; 2] {
+    [
+        Command::ColumnAddress(area.start.0, area.end.0 - 1),
+        Command::RowAddress(area.start.1, area.end.1 - 1),
+    ]
+}
 
-//     fn draw_pixel(&mut self, pixel: Pixel<BinaryColor>) -> Result<(), Self::Error> {
-//         let Pixel(coord, color) = pixel;
+/// Contrast for a brightness in percent of the maximum contrast
+pub(crate) fn brightness_to_contrast(percent: u8) -> u8 {
+    (percent.min(100) as u16 * 0xFF / 100) as u8
+}
 
-//         let i = coord.y as u32 * self.size().width + coord.x as u32;
-//         if i < self.displaybuffer.len() as u32{
-//             self.displaybuffer[i as usize] = color.is_on();
-//         }
-//         Ok(())
-//     }
+#[cfg(feature = "graphics")]
+impl<DI> DrawTarget for Display<DI> {
+    type Color = Gray4;
+    type Error = core::convert::Infallible;
 
-//     fn size(&self) -> Size {
-//         let (w,h) = self.dimensions();
-//         Size::new(w as u32, h as u32)
-//     }
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(coord, color) in pixels {
+            // negative coordinates are outside of the display
+            if coord.x >= 0 && coord.y >= 0 {
+                self.set_pixel(coord.x as usize, coord.y as usize, color.luma());
+            }
+        }
+        Ok(())
+    }
 
-// }
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        Display::clear(self, color.luma());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<DI> OriginDimensions for Display<DI> {
+    fn size(&self) -> Size {
+        let (w, h) = self.dimensions();
+        Size::new(w as u32, h as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::VcomhLevel;
+    use display_interface::{DataFormat, DisplayError};
+    use std::vec::Vec;
+
+    /// Interface that records everything sent to it
+    #[derive(Default)]
+    struct Recorder {
+        commands: Vec<u8>,
+        data: Vec<u8>,
+        /// Number of `send_commands` calls
+        transactions: usize,
+    }
+
+    impl WriteOnlyDataCommand for Recorder {
+        fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+            match cmd {
+                U8(bytes) => self.commands.extend_from_slice(bytes),
+                _ => return Err(DisplayError::DataFormatNotImplemented),
+            }
+            self.transactions += 1;
+            Ok(())
+        }
+
+        fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+            match buf {
+                U8(bytes) => self.data.extend_from_slice(bytes),
+                _ => return Err(DisplayError::DataFormatNotImplemented),
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn it_sends_the_init_sequence_from_the_config() {
+        let mut display = Display::new(Recorder::default(), DisplayRotation::Rotate0);
+        display.init().unwrap();
+
+        assert_eq!(
+            display.iface.commands,
+            [
+                0xAB, 0x01, 0xAD, 0x9E, 0x15, 0x00, 0x7F, 0x75, 0x00, 0x3F, 0xA0, 0x54, 0xA1, 0x00,
+                0xA2, 0x00, 0xA4, 0xA8, 0x3F, 0xB1, 0x11, 0xB3, 0xF0, 0xB9, 0xBC, 0x04, 0xBE, 0x05,
+                0x81, 0x7F
+            ]
+        );
+
+        let config = DisplayConfig::new()
+            .phase_length(0x22)
+            .precharge_period(0x08)
+            .grayscale(GrayscaleTable::LINEAR)
+            .precharge_capacitor(true)
+            .vcomh(VcomhLevel::V072)
+            .contrast(0x20);
+        let mut display = Display::with_config(Recorder::default(), DisplayRotation::Rotate0, config);
+        display.init().unwrap();
+
+        assert_eq!(
+            display.iface.commands,
+            [
+                0xAB, 0x01, 0xAD, 0x9E, 0x15, 0x00, 0x7F, 0x75, 0x00, 0x3F, 0xA0, 0x54, 0xA1, 0x00,
+                0xA2, 0x00, 0xA4, 0xA8, 0x3F, 0xB1, 0x22, 0xB3, 0xF0, 0xB6, 0x08, 0xB8, 4, 8, 12,
+                16, 20, 24, 28, 32, 36, 40, 44, 48, 52, 56, 60, 0xBC, 0x04, 0xBD, 0x01, 0xBE, 0x00,
+                0x81, 0x20
+            ]
+        );
+        assert_eq!(display.contrast(), 0x20);
+    }
+
+    #[test]
+    fn it_reapplies_the_shadow_registers() {
+        let mut display = Display::new(Recorder::default(), DisplayRotation::Rotate0);
+        display.init().unwrap();
+        display.set_contrast(0x20).unwrap();
+        display.scroll(5).unwrap();
+        display.on().unwrap();
+
+        let registers = display.registers();
+        assert_eq!((registers.contrast(), registers.display_offset(), registers.is_on()), (0x20, 5, true));
+        assert_eq!(registers.phase_length(), Some(0x11));
+        assert_eq!(registers.precharge_period(), None);
+
+        display.iface.commands.clear();
+        display.iface.transactions = 0;
+        display.reapply_state().unwrap();
+
+        assert_eq!(
+            display.iface.commands,
+            [
+                0xAB, 0x01, 0xAD, 0x9E, 0x15, 0x00, 0x7F, 0x75, 0x00, 0x3F, 0xA0, 0x54, 0xA1, 0x00,
+                0xA2, 0x05, 0xA4, 0xA8, 0x3F, 0xB1, 0x11, 0xB3, 0xF0, 0xB9, 0xBC, 0x04, 0xBE, 0x05,
+                0x81, 0x20, 0xAF
+            ]
+        );
+        assert_eq!(display.iface.transactions, 1);
+    }
+
+    #[test]
+    fn it_refreshes_without_turning_the_display_off() {
+        let mut display = Display::new(Recorder::default(), DisplayRotation::Rotate0);
+        display.init().unwrap();
+        display.on().unwrap();
+        display.set_pixel(0, 0, 0xF);
+        display.flush().unwrap();
+
+        display.iface.commands.clear();
+        display.iface.data.clear();
+        display.refresh_config().unwrap();
+
+        // state first, then the complete framebuffer in one window, display on last
+        let refresh = display.iface.commands.clone();
+        assert_eq!(refresh[..3], [0xAB, 0x01, 0xAD]);
+        assert_eq!(refresh[refresh.len() - 9..], [0x81, 0x7F, 0x15, 0x00, 0x7F, 0x75, 0x00, 0x3F, 0xAF]);
+        assert!(!refresh.contains(&0xAE));
+        assert_eq!(display.iface.data.len(), RAM_COLUMNS * RAM_ROWS);
+        assert_eq!(display.iface.data[0], 0xF0);
+
+        // a running scroll is stopped for the upload and started again, the blink is left alone
+        display.start_horizontal_scroll(ScrollDirection::Left, (0, 0), (8, 8), ScrollInterval::Frames2).unwrap();
+        display.set_fade_mode(FadeMode::Blink, 3).unwrap();
+        display.iface.commands.clear();
+        display.iface.data.clear();
+        display.refresh_config().unwrap();
+
+        let commands = &display.iface.commands;
+        assert_eq!(commands[0], 0x2E);
+        assert_eq!(commands[1..refresh.len()], refresh[..refresh.len() - 1]);
+        assert_eq!(commands[refresh.len()..], [0x27, 0x00, 0, 0b100, 7, 0, 7, 0x00, 0x2F, 0xAF]);
+        assert!(!commands.contains(&0x23));
+        assert_eq!(display.iface.data.len(), RAM_COLUMNS * RAM_ROWS);
+        assert!(display.registers().is_scrolling());
+    }
+
+    #[test]
+    fn it_releases_the_interface() {
+        let mut display = Display::new(Recorder::default(), DisplayRotation::Rotate0);
+        display.on().unwrap();
+
+        let iface = display.release();
+        assert_eq!(iface.commands, [0xAF]);
+
+        let mut display = Display::new(Recorder::default(), DisplayRotation::Rotate0);
+        display.on().unwrap();
+
+        let (iface, registers) = display.into_parts();
+        assert_eq!(iface.commands, [0xAF]);
+        assert!(registers.is_on());
+    }
+
+    #[test]
+    fn it_batches_commands_into_one_transaction() {
+        let mut display = Display::new(Recorder::default(), DisplayRotation::Rotate0);
+
+        display.init().unwrap();
+        assert_eq!(display.iface.transactions, 1);
+
+        display.set_draw_area((4, 8), (12, 16)).unwrap();
+        assert_eq!(display.iface.transactions, 2);
+        assert_eq!(display.iface.commands[display.iface.commands.len() - 6..], [0x15, 4, 11, 0x75, 8, 15]);
+
+        // every dirty area is a single window setup followed by its data
+        display.set_pixel(0, 0, 0xF);
+        display.set_pixel(255, 63, 0xF);
+        display.flush().unwrap();
+        assert_eq!(display.iface.transactions, 3);
+    }
+
+    #[test]
+    fn it_uses_the_display_size() {
+        assert!(matches!(DisplaySize::new(255, 64, 0), Err(Error::InvalidArgument)));
+        assert!(matches!(DisplaySize::new(256, 64, 1), Err(Error::InvalidArgument)));
+        assert!(matches!(DisplaySize::new(128, 65, 0), Err(Error::InvalidArgument)));
+        assert!(matches!(DisplaySize::new(128, 32, 0).unwrap().with_multiplex(31), Err(Error::InvalidArgument)));
+
+        let size = DisplaySize::new(192, 48, 8).unwrap().with_multiplex(50).unwrap();
+        let config = DisplayConfig::new().size(size);
+        let mut display = Display::with_config(Recorder::default(), DisplayRotation::Rotate0, config);
+        display.init().unwrap();
+
+        assert_eq!(display.dimensions(), (192, 48));
+        assert_eq!(&display.iface.commands[4..10], [0x15, 8, 103, 0x75, 0, 47]);
+        assert_eq!(&display.iface.commands[17..19], [0xA8, 49]);
+
+        // only the panel is blanked
+        display.flush().unwrap();
+        display.iface = Recorder::default();
+        display.blank().unwrap();
+        assert_eq!(display.iface.commands, [0x15, 8, 103, 0x75, 0, 47]);
+        assert_eq!(display.iface.data.len(), 96 * 48);
+
+        display.set_pixel(0, 0, 1);
+        assert_eq!(display.framebuffer.get_pixel(16, 0), Some(1));
+        assert_eq!(display.get_pixel(191, 47), Some(0));
+        assert_eq!(display.get_pixel(192, 0), None);
+
+        // column remap mirrors the panel to the other end of the RAM
+        let mut display = Display::with_config(Recorder::default(), DisplayRotation::Rotate180, config);
+        display.set_draw_area((0, 0), (96, 48)).unwrap();
+        assert_eq!(display.iface.commands, [0x15, 24, 119, 0x75, 0, 47]);
+    }
+
+    #[test]
+    fn it_rejects_areas_outside_of_the_panel() {
+        let size = DisplaySize::new(192, 48, 8).unwrap();
+        let config = DisplayConfig::new().size(size);
+        let mut display = Display::with_config(Recorder::default(), DisplayRotation::Rotate0, config);
+
+        assert!(matches!(display.set_draw_area((0, 0), (97, 48)), Err(Error::InvalidArgument)));
+        assert!(matches!(display.set_draw_area((0, 0), (96, 49)), Err(Error::InvalidArgument)));
+        assert!(matches!(display.set_draw_area((10, 0), (10, 48)), Err(Error::InvalidArgument)));
+        assert!(matches!(
+            display.start_horizontal_scroll(ScrollDirection::Left, (0, 8), (0, 16), ScrollInterval::Frames2),
+            Err(Error::InvalidArgument)
+        ));
+        assert!(display.iface.commands.is_empty());
+
+        display.set_draw_area((0, 0), (96, 48)).unwrap();
+    }
+
+    #[test]
+    fn it_rejects_out_of_range_parameters() {
+        let config = DisplayConfig::new().precharge_voltage(0x20);
+        let mut display = Display::with_config(Recorder::default(), DisplayRotation::Rotate0, config);
+
+        assert!(matches!(display.init(), Err(Error::InvalidArgument)));
+        assert!(matches!(display.scroll(64), Err(Error::InvalidArgument)));
+        assert!(matches!(display.set_fade_mode(FadeMode::FadeOut, 16), Err(Error::InvalidArgument)));
+        assert!(display.iface.commands.is_empty());
+    }
+
+    #[test]
+    fn it_flushes_in_horizontal_addressing_mode() {
+        let remap = RemapConfig { com_remap: true, ..RemapConfig::default() };
+        let config = DisplayConfig::new().remap(remap);
+        let mut display = Display::with_config(Recorder::default(), DisplayRotation::Rotate0, config);
+        display.init().unwrap();
+        assert_eq!(&display.iface.commands[10..12], [0xA0, 0x10]);
+
+        display.flush().unwrap();
+        display.iface = Recorder::default();
+
+        display.set_pixel(20, 5, 0x1);
+        display.set_pixel(23, 6, 0x2);
+        display.flush().unwrap();
+
+        assert_eq!(display.iface.commands, [0x15, 10, 11, 0x75, 5, 6]);
+        assert_eq!(display.iface.data, [0x10, 0x00, 0x00, 0x02]);
+    }
+
+    #[test]
+    fn it_scrolls_horizontally() {
+        let mut display = Display::new(Recorder::default(), DisplayRotation::Rotate0);
+        display.flush().unwrap();
+        display.iface = Recorder::default();
+
+        display
+            .start_horizontal_scroll(ScrollDirection::Left, (0, 8), (128, 16), ScrollInterval::Frames2)
+            .unwrap();
+        assert_eq!(
+            display.iface.commands,
+            [0x27, 0x00, 8, 0b100, 15, 0, 127, 0x00, 0x2F]
+        );
+
+        // nothing is written to the display RAM while scrolling
+        display.iface = Recorder::default();
+        display.flush().unwrap();
+        display.set_pixel(200, 40, 0xF);
+        assert!(matches!(display.flush(), Err(Error::Busy)));
+        assert!(display.iface.commands.is_empty() && display.iface.data.is_empty());
+
+        display.stop_horizontal_scroll().unwrap();
+        display.flush().unwrap();
+
+        // the area modified while scrolling is kept, the scrolled area is uploaded again
+        assert_eq!(display.iface.commands, [0x2E, 0x15, 100, 100, 0x75, 40, 40, 0x15, 0, 127, 0x75, 8, 15]);
+        assert_eq!(display.iface.data.len(), 128 * 8 + 1);
+    }
+
+    #[test]
+    fn it_sets_the_fade_mode() {
+        let mut display = Display::new(Recorder::default(), DisplayRotation::Rotate0);
+
+        display.set_fade_mode(FadeMode::Blink, 3).unwrap();
+        display.set_fade_mode(FadeMode::FadeOut, 15).unwrap();
+        display.set_fade_mode(FadeMode::Disabled, 0).unwrap();
+        assert_eq!(display.iface.commands, [0x23, 0x33, 0x23, 0x2F, 0x23, 0x00]);
+    }
+
+    #[test]
+    fn it_sets_and_gets_pixels() {
+        let mut display = Display::new(Recorder::default(), DisplayRotation::Rotate0);
+
+        display.set_pixel(0, 0, 0x3);
+        display.set_pixel(1, 0, 0xA);
+        display.set_pixel(255, 63, 0xF);
+
+        assert_eq!(display.get_pixel(0, 0), Some(0x3));
+        assert_eq!(display.get_pixel(1, 0), Some(0xA));
+        assert_eq!(display.get_pixel(255, 63), Some(0xF));
+        assert_eq!(display.get_pixel(2, 0), Some(0));
+    }
+
+    #[test]
+    fn it_ignores_pixels_outside_the_display() {
+        let mut display = Display::new(Recorder::default(), DisplayRotation::Rotate0);
+
+        display.set_pixel(256, 0, 0xF);
+        display.set_pixel(0, 64, 0xF);
+
+        assert_eq!(display.get_pixel(256, 0), None);
+        assert_eq!(display.get_pixel(0, 64), None);
+    }
+
+    #[test]
+    fn it_sends_the_grayscale_table() {
+        let mut display = Display::new(Recorder::default(), DisplayRotation::Rotate0);
+
+        display.set_grayscale_table(&GrayscaleTable::GAMMA_2_2).unwrap();
+        assert_eq!(
+            display.iface.commands,
+            [0xB8, 1, 2, 3, 4, 7, 9, 13, 17, 21, 26, 32, 39, 46, 54, 63]
+        );
+    }
+
+    #[test]
+    fn it_fades_the_contrast() {
+        let fade: std::vec::Vec<u8> = ContrastFade::new(0, 0xFF, 4).collect();
+        assert_eq!(fade, [63, 127, 191, 255]);
+
+        let fade: std::vec::Vec<u8> = ContrastFade::new(0x80, 0x00, 0).collect();
+        assert_eq!(fade, [0]);
+
+        let mut display = Display::new(Recorder::default(), DisplayRotation::Rotate0);
+        display.set_contrast(0x10).unwrap();
+        display.start_fade(0x30, 2);
+
+        assert!(display.fade_tick().unwrap());
+        assert_eq!(display.contrast(), 0x20);
+        assert!(!display.fade_tick().unwrap());
+        assert_eq!(display.contrast(), 0x30);
+        assert!(!display.fade_tick().unwrap());
+
+        assert_eq!(display.iface.commands, [0x81, 0x10, 0x81, 0x20, 0x81, 0x30]);
+    }
+
+    #[test]
+    fn it_rotates_in_software() {
+        let mut display = Display::new(Recorder::default(), DisplayRotation::Rotate90);
+        assert_eq!(display.dimensions(), (64, 256));
+
+        display.set_pixel(0, 0, 0x1);
+        display.set_pixel(63, 255, 0x2);
+        assert_eq!(display.framebuffer.get_pixel(255, 0), Some(0x1));
+        assert_eq!(display.framebuffer.get_pixel(0, 63), Some(0x2));
+        assert_eq!(display.get_pixel(0, 0), Some(0x1));
+        assert_eq!(display.get_pixel(64, 0), None);
+
+        let mut display = Display::new(Recorder::default(), DisplayRotation::Rotate270);
+        assert_eq!(display.dimensions(), (64, 256));
+
+        display.set_pixel(0, 0, 0x1);
+        display.set_pixel(63, 255, 0x2);
+        assert_eq!(display.framebuffer.get_pixel(0, 63), Some(0x1));
+        assert_eq!(display.framebuffer.get_pixel(255, 0), Some(0x2));
+        assert_eq!(display.get_pixel(63, 255), Some(0x2));
+    }
+
+    #[test]
+    fn it_clears_to_a_gray_level() {
+        let mut display = Display::new(Recorder::default(), DisplayRotation::Rotate0);
+
+        display.clear(0x7);
+        assert_eq!(display.get_pixel(10, 10), Some(0x7));
+        assert_eq!(display.get_pixel(255, 63), Some(0x7));
+    }
+
+    #[test]
+    fn it_flushes_the_whole_buffer_first() {
+        let mut display = Display::new(Recorder::default(), DisplayRotation::Rotate0);
+
+        display.set_pixel(2, 1, 0xC);
+        display.flush().unwrap();
+
+        assert_eq!(display.iface.commands, [0x15, 0, 127, 0x75, 0, 63]);
+        assert_eq!(display.iface.data.len(), 128 * 64);
+        assert_eq!(display.iface.data[64 + 1], 0xC0);
+    }
+
+    #[test]
+    fn it_flushes_only_modified_areas() {
+        let mut display = Display::new(Recorder::default(), DisplayRotation::Rotate0);
+        display.flush().unwrap();
+        display.iface = Recorder::default();
+
+        // nothing changed, nothing to send
+        display.flush().unwrap();
+        assert!(display.iface.commands.is_empty());
+
+        display.set_pixel(20, 5, 0x1);
+        display.set_pixel(23, 6, 0x2);
+        display.set_pixel(200, 40, 0x3);
+        display.flush().unwrap();
+
+        assert_eq!(
+            display.iface.commands,
+            [0x15, 10, 11, 0x75, 5, 6, 0x15, 100, 100, 0x75, 40, 40]
+        );
+        assert_eq!(display.iface.data, [0x10, 0x00, 0x00, 0x02, 0x30]);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn it_draws_gray4_pixels() {
+        use embedded_graphics::{prelude::*, primitives::{PrimitiveStyle, Rectangle}};
+
+        let mut display = Display::new(Recorder::default(), DisplayRotation::Rotate0);
+        assert_eq!(display.size(), Size::new(256, 64));
+
+        Rectangle::new(Point::new(-2, 62), Size::new(4, 4))
+            .into_styled(PrimitiveStyle::with_fill(Gray4::new(9)))
+            .draw(&mut display)
+            .unwrap();
+
+        assert_eq!(display.get_pixel(0, 62), Some(9));
+        assert_eq!(display.get_pixel(1, 63), Some(9));
+        assert_eq!(display.get_pixel(2, 63), Some(0));
+    }
+}
@@ -0,0 +1,635 @@
+//! Host-side model of the SSD1362 controller
+//!
+//! `Emulator` implements `WriteOnlyDataCommand` and decodes the command stream the way the
+//! controller would: it keeps track of the column and row windows, the re-map settings, start
+//! line, display offset and contrast, and writes data bytes into a virtual 128x64 GDDRAM.
+//! This lets `Display` and `TerminalView` rendering be tested without hardware.
+//!
+//! ```ignore
+//! let mut emulator = Emulator::new();
+//! let mut display = Display::new(&mut emulator, DisplayRotation::Rotate0);
+//! display.init()?;
+//! display.set_pixel(0, 0, 0xF);
+//! display.flush()?;
+//! assert_eq!(emulator.ram(0, 0), 0xF0);
+//! ```
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use std::vec::Vec;
+
+use crate::command::{DisplayMode, RemapConfig};
+use crate::framebuffer::{RAM_COLUMNS, RAM_ROWS};
+
+/// Number of parameter bytes following a command byte, unknown commands have none
+fn parameter_count(command: u8) -> usize {
+    match command {
+        0x15 | 0x75 | 0xA3 => 2,
+        0x26 | 0x27 => 7,
+        0xB8 => 15,
+        0x23 | 0x81 | 0xA0 | 0xA1 | 0xA2 | 0xA8 | 0xAB | 0xAD | 0xB1 | 0xB3 | 0xB6 | 0xBC
+        | 0xBD | 0xBE | 0xFD => 1,
+        _ => 0,
+    }
+}
+
+/// Model of the SSD1362 controller state and display RAM
+pub struct Emulator {
+    /// GDDRAM row by row, one byte per column of two pixels
+    ram: [u8; RAM_COLUMNS * RAM_ROWS],
+    columns: (u8, u8),
+    rows: (u8, u8),
+    /// Address the next data byte is written to
+    address: (u8, u8),
+    remap: RemapConfig,
+    start_line: u8,
+    display_offset: u8,
+    contrast: u8,
+    mode: DisplayMode,
+    multiplex: u8,
+    on: bool,
+    scrolling: bool,
+    grayscale: Option<[u8; 15]>,
+    locked: bool,
+    /// Bytes of a command whose parameters have not all arrived yet
+    pending: Vec<u8>,
+    /// Command bytes the controller does not know
+    unknown: Vec<u8>,
+}
+
+impl Emulator {
+    /// Controller in its reset state, with an empty display RAM
+    pub fn new() -> Self {
+        Emulator {
+            ram: [0; RAM_COLUMNS * RAM_ROWS],
+            columns: (0, RAM_COLUMNS as u8 - 1),
+            rows: (0, RAM_ROWS as u8 - 1),
+            address: (0, 0),
+            remap: RemapConfig::default(),
+            start_line: 0,
+            display_offset: 0,
+            contrast: 0x7F,
+            mode: DisplayMode::Normal,
+            multiplex: RAM_ROWS as u8 - 1,
+            on: false,
+            scrolling: false,
+            grayscale: None,
+            locked: false,
+            pending: Vec::new(),
+            unknown: Vec::new(),
+        }
+    }
+
+    /// Byte at a column and row of the display RAM, the left pixel is in the high nibble
+    pub fn ram(&self, column: usize, row: usize) -> u8 {
+        self.ram[row * RAM_COLUMNS + column]
+    }
+
+    /// Gray level of a pixel in display RAM coordinates, before any re-map
+    pub fn ram_pixel(&self, x: usize, y: usize) -> u8 {
+        let byte = self.ram(x / 2, y);
+        if x & 1 == 0 { byte >> 4 } else { byte & 0x0F }
+    }
+
+    /// Column window (start, end) set by the last column address command, the end is inclusive
+    pub fn column_window(&self) -> (u8, u8) {
+        self.columns
+    }
+
+    /// Row window (start, end) set by the last row address command, the end is inclusive
+    pub fn row_window(&self) -> (u8, u8) {
+        self.rows
+    }
+
+    pub fn remap(&self) -> RemapConfig {
+        self.remap
+    }
+
+    pub fn start_line(&self) -> u8 {
+        self.start_line
+    }
+
+    pub fn display_offset(&self) -> u8 {
+        self.display_offset
+    }
+
+    pub fn contrast(&self) -> u8 {
+        self.contrast
+    }
+
+    pub fn mode(&self) -> DisplayMode {
+        self.mode
+    }
+
+    /// Multiplex ratio as sent, the number of COM lines - 1
+    pub fn multiplex(&self) -> u8 {
+        self.multiplex
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    /// True while a horizontal scroll is active
+    pub fn is_scrolling(&self) -> bool {
+        self.scrolling
+    }
+
+    /// Gray scale table, `None` for the default table
+    pub fn grayscale(&self) -> Option<[u8; 15]> {
+        self.grayscale
+    }
+
+    /// True when commands are locked by the command lock
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Command bytes received that the controller does not know
+    pub fn unknown_commands(&self) -> &[u8] {
+        &self.unknown
+    }
+
+    /// Size in pixels of what the panel shows: all 256 SEG lines by the driven COM lines
+    pub fn screen_size(&self) -> (usize, usize) {
+        (RAM_COLUMNS * 2, self.multiplex as usize + 1)
+    }
+
+    /// Gray level the viewer sees at a position on the screen.
+    ///
+    /// The panel is modelled after the OEL9M1020 module, which shows the image upright with
+    /// COM re-map on (see `Display::remap`). Column re-map mirrors the screen horizontally,
+    /// nibble re-map swaps the two pixels of every byte and turning COM re-map off flips it
+    /// vertically. Start line and display offset both move the image up. SEG split and
+    /// left/right swap only depend on how the panel is wired and are ignored, as are the
+    /// contrast and the gray scale table.
+    pub fn screen_pixel(&self, x: usize, y: usize) -> u8 {
+        let (width, height) = self.screen_size();
+        assert!(x < width && y < height, "position outside of the screen");
+
+        let level = match self.mode {
+            _ if !self.on => return 0,
+            DisplayMode::AllOn => return 0x0F,
+            DisplayMode::AllOff => return 0,
+            DisplayMode::Normal | DisplayMode::Inverse => {
+                let row = if self.remap.com_remap { y } else { height - 1 - y };
+                let row = (row + self.start_line as usize + self.display_offset as usize) % RAM_ROWS;
+
+                let x = if self.remap.column_remap { width - 1 - x } else { x };
+                let x = if self.remap.nibble_remap { x ^ 1 } else { x };
+                self.ram_pixel(x, row)
+            }
+        };
+
+        match self.mode {
+            DisplayMode::Inverse => 0x0F - level,
+            _ => level,
+        }
+    }
+
+    /// The screen as a binary PGM (P5) image, gray levels scaled to 0 - 255
+    pub fn to_pgm(&self) -> Vec<u8> {
+        let (width, height) = self.screen_size();
+
+        let mut pgm = format!("P5\n{} {}\n255\n", width, height).into_bytes();
+        pgm.extend(self.screen_bytes());
+        pgm
+    }
+
+    /// The screen as an 8 bit grayscale PNG image, gray levels scaled to 0 - 255
+    pub fn to_png(&self) -> Vec<u8> {
+        let (width, height) = self.screen_size();
+
+        // every row starts with filter type 0 (none)
+        let mut raw = Vec::with_capacity((width + 1) * height);
+        for row in self.screen_bytes().collect::<Vec<u8>>().chunks(width) {
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+
+        let mut header = Vec::new();
+        header.extend_from_slice(&(width as u32).to_be_bytes());
+        header.extend_from_slice(&(height as u32).to_be_bytes());
+        header.extend_from_slice(&[8, 0, 0, 0, 0]); // bit depth 8, grayscale, no interlace
+
+        let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+        png_chunk(&mut png, b"IHDR", &header);
+        png_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+        png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    /// Screen pixels row by row, scaled to 0 - 255
+    fn screen_bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        let (width, height) = self.screen_size();
+        (0..height).flat_map(move |y| (0..width).map(move |x| self.screen_pixel(x, y) * 17))
+    }
+
+    /// Decode one byte of the command stream.
+    /// Commands may be split over several `send_commands` calls, or batched into one.
+    fn command_byte(&mut self, byte: u8) {
+        self.pending.push(byte);
+
+        if self.pending.len() > parameter_count(self.pending[0]) {
+            let command = core::mem::take(&mut self.pending);
+            self.execute(command[0], &command[1..]);
+        }
+    }
+
+    fn execute(&mut self, command: u8, params: &[u8]) {
+        // only the unlock command is accepted while locked
+        if self.locked && command != 0xFD {
+            return;
+        }
+
+        match command {
+            0x15 => {
+                self.columns = (params[0] & 0x7F, params[1] & 0x7F);
+                self.address = (self.columns.0, self.rows.0);
+            }
+            0x75 => {
+                self.rows = (params[0] & 0x3F, params[1] & 0x3F);
+                self.address = (self.columns.0, self.rows.0);
+            }
+            0x81 => self.contrast = params[0],
+            0xA0 => self.remap = RemapConfig::from_byte(params[0]),
+            0xA1 => self.start_line = params[0] & 0x3F,
+            0xA2 => self.display_offset = params[0] & 0x3F,
+            0xA4 => self.mode = DisplayMode::Normal,
+            0xA5 => self.mode = DisplayMode::AllOn,
+            0xA6 => self.mode = DisplayMode::AllOff,
+            0xA7 => self.mode = DisplayMode::Inverse,
+            0xA8 => self.multiplex = params[0] & 0x3F,
+            0xAE => self.on = false,
+            0xAF => self.on = true,
+            0x2E => self.scrolling = false,
+            0x2F => self.scrolling = true,
+            0xB8 => {
+                let mut table = [0; 15];
+                table.copy_from_slice(params);
+                self.grayscale = Some(table);
+            }
+            0xB9 => self.grayscale = None,
+            0xFD => self.locked = params[0] & 0x04 != 0,
+            // accepted, but without effect on the display RAM
+            0x23 | 0x26 | 0x27 | 0xA3 | 0xAB | 0xAD | 0xB1 | 0xB3 | 0xB6 | 0xBC | 0xBD | 0xBE => {}
+            _ => self.unknown.push(command),
+        }
+    }
+
+    /// Write a data byte at the current address and advance it within the window
+    fn data_byte(&mut self, byte: u8) {
+        if self.locked {
+            return;
+        }
+
+        let (col, row) = self.address;
+        self.ram[row as usize * RAM_COLUMNS + col as usize] = byte;
+
+        let next_col = |col: u8| if col >= self.columns.1 { None } else { Some(col + 1) };
+        let next_row = |row: u8| if row >= self.rows.1 { None } else { Some(row + 1) };
+
+        self.address = if self.remap.vertical_addressing {
+            match next_row(row) {
+                Some(row) => (col, row),
+                None => (next_col(col).unwrap_or(self.columns.0), self.rows.0),
+            }
+        } else {
+            match next_col(col) {
+                Some(col) => (col, row),
+                None => (self.columns.0, next_row(row).unwrap_or(self.rows.0)),
+            }
+        };
+    }
+}
+
+/// Append a PNG chunk: length, type, data and CRC over type and data
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// zlib stream with uncompressed deflate blocks, the images are small enough not to bother
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut zlib = vec![0x78, 0x01];
+
+    let mut blocks = data.chunks(0xFFFF).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+        zlib.push(blocks.peek().is_none() as u8);
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), byte| {
+        let a = (a + *byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    zlib.extend_from_slice(&((b << 16) | a).to_be_bytes());
+    zlib
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+impl Default for Emulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WriteOnlyDataCommand for Emulator {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        match cmd {
+            DataFormat::U8(bytes) => bytes.iter().for_each(|b| self.command_byte(*b)),
+            DataFormat::U8Iter(iter) => iter.for_each(|b| self.command_byte(b)),
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        }
+        Ok(())
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        match buf {
+            DataFormat::U8(bytes) => bytes.iter().for_each(|b| self.data_byte(*b)),
+            DataFormat::U8Iter(iter) => iter.for_each(|b| self.data_byte(b)),
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        }
+        Ok(())
+    }
+}
+
+/// Lets a `Display` borrow the emulator, so it can be inspected while the display is in use
+impl WriteOnlyDataCommand for &mut Emulator {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        (**self).send_commands(cmd)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        (**self).send_data(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::{Command, ScrollDirection, ScrollInterval};
+    use crate::config::DisplayConfig;
+    use crate::display::{Display, DisplayRotation};
+
+    #[test]
+    fn it_tracks_the_controller_state() {
+        let mut emulator = Emulator::new();
+
+        Command::ColumnAddress(10, 20).send(&mut emulator).unwrap();
+        Command::RowAddress(5, 6).send(&mut emulator).unwrap();
+        Command::Contrast(0x33).send(&mut emulator).unwrap();
+        Command::StartLine(8).send(&mut emulator).unwrap();
+        Command::DisplayOffset(4).send(&mut emulator).unwrap();
+        Command::Remap(RemapConfig::from_byte(0x54)).send(&mut emulator).unwrap();
+        Command::DisplayOn(true).send(&mut emulator).unwrap();
+
+        assert_eq!(emulator.column_window(), (10, 20));
+        assert_eq!(emulator.row_window(), (5, 6));
+        assert_eq!(emulator.contrast(), 0x33);
+        assert_eq!(emulator.start_line(), 8);
+        assert_eq!(emulator.display_offset(), 4);
+        assert_eq!(emulator.remap().to_byte(), 0x54);
+        assert!(emulator.is_on());
+
+        // commands batched into one transfer, and split over two
+        emulator.send_commands(DataFormat::U8(&[0x81, 0x10, 0xAE, 0xA1])).unwrap();
+        emulator.send_commands(DataFormat::U8(&[0x02])).unwrap();
+        assert_eq!(emulator.contrast(), 0x10);
+        assert!(!emulator.is_on());
+        assert_eq!(emulator.start_line(), 2);
+    }
+
+    #[test]
+    fn it_wraps_data_within_the_window() {
+        let mut emulator = Emulator::new();
+
+        // horizontal addressing: along the row, then the next row
+        Command::ColumnAddress(1, 2).send(&mut emulator).unwrap();
+        Command::RowAddress(3, 4).send(&mut emulator).unwrap();
+        emulator.send_data(DataFormat::U8(&[1, 2, 3, 4, 5])).unwrap();
+
+        assert_eq!([emulator.ram(1, 3), emulator.ram(2, 3)], [5, 2]);
+        assert_eq!([emulator.ram(1, 4), emulator.ram(2, 4)], [3, 4]);
+
+        // vertical addressing: down the column, then the next column
+        Command::Remap(RemapConfig { vertical_addressing: true, ..RemapConfig::default() })
+            .send(&mut emulator)
+            .unwrap();
+        Command::ColumnAddress(1, 2).send(&mut emulator).unwrap();
+        emulator.send_data(DataFormat::U8(&[6, 7, 8])).unwrap();
+
+        assert_eq!([emulator.ram(1, 3), emulator.ram(1, 4), emulator.ram(2, 3)], [6, 7, 8]);
+    }
+
+    #[test]
+    fn it_shows_what_the_viewer_sees() {
+        let mut emulator = Emulator::new();
+        let remap = RemapConfig { com_remap: true, ..RemapConfig::default() };
+        Command::Remap(remap).send(&mut emulator).unwrap();
+        Command::DisplayOn(true).send(&mut emulator).unwrap();
+        emulator.send_data(DataFormat::U8(&[0x12])).unwrap();
+
+        assert_eq!([emulator.screen_pixel(0, 0), emulator.screen_pixel(1, 0)], [1, 2]);
+
+        // start line and offset move the image up
+        Command::StartLine(60).send(&mut emulator).unwrap();
+        Command::DisplayOffset(2).send(&mut emulator).unwrap();
+        assert_eq!(emulator.screen_pixel(0, 2), 1);
+
+        Command::StartLine(0).send(&mut emulator).unwrap();
+        Command::DisplayOffset(0).send(&mut emulator).unwrap();
+        Command::Remap(RemapConfig { column_remap: true, ..RemapConfig::default() })
+            .send(&mut emulator)
+            .unwrap();
+        assert_eq!([emulator.screen_pixel(255, 63), emulator.screen_pixel(254, 63)], [1, 2]);
+
+        Command::Remap(RemapConfig { nibble_remap: true, ..remap }).send(&mut emulator).unwrap();
+        assert_eq!([emulator.screen_pixel(0, 0), emulator.screen_pixel(1, 0)], [2, 1]);
+
+        emulator.send_commands(DataFormat::U8(&[0xA7])).unwrap();
+        assert_eq!([emulator.screen_pixel(0, 0), emulator.screen_pixel(2, 0)], [0xD, 0xF]);
+
+        emulator.send_commands(DataFormat::U8(&[0xA4, 0xAE])).unwrap();
+        assert_eq!(emulator.screen_pixel(1, 0), 0);
+    }
+
+    #[test]
+    fn it_exports_images() {
+        let mut emulator = Emulator::new();
+        Command::Multiplex(1).send(&mut emulator).unwrap();
+        Command::Remap(RemapConfig { com_remap: true, ..RemapConfig::default() })
+            .send(&mut emulator)
+            .unwrap();
+        Command::DisplayOn(true).send(&mut emulator).unwrap();
+        emulator.send_data(DataFormat::U8(&[0xF1])).unwrap();
+
+        let pgm = emulator.to_pgm();
+        assert!(pgm.starts_with(b"P5\n256 2\n255\n"));
+        assert_eq!(pgm.len(), 13 + 256 * 2);
+        assert_eq!(pgm[13..16], [255, 17, 0]);
+
+        let png = emulator.to_png();
+        assert_eq!(png[..8], [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n']);
+        assert_eq!(png[8..16], [0, 0, 0, 13, b'I', b'H', b'D', b'R']);
+        assert_eq!(png[16..29], [0, 0, 1, 0, 0, 0, 0, 2, 8, 0, 0, 0, 0]);
+        assert_eq!(png[png.len() - 12..], [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]);
+    }
+
+    #[test]
+    fn it_mirrors_the_display_framebuffer() {
+        let mut emulator = Emulator::new();
+        let size = crate::display::DisplaySize::new(128, 32, 8).unwrap();
+        let config = DisplayConfig::new().size(size);
+        let mut display = Display::with_config(&mut emulator, DisplayRotation::Rotate90, config);
+
+        display.init().unwrap();
+        display.on().unwrap();
+        for i in 0..32 {
+            display.set_pixel(i, i * 3, (i % 16) as u8);
+        }
+        display.flush().unwrap();
+        display.set_pixel(31, 127, 0xF);
+        display.flush().unwrap();
+
+        let pixels = |y| (0..RAM_COLUMNS * 2).map(move |x| (x, y));
+        let expected: Vec<u8> = (0..RAM_ROWS)
+            .flat_map(pixels)
+            .map(|(x, y)| display.framebuffer.get_pixel(x, y).unwrap())
+            .collect();
+        let emulated: Vec<u8> = (0..RAM_ROWS).flat_map(pixels).map(|(x, y)| emulator.ram_pixel(x, y)).collect();
+
+        assert_eq!(emulated, expected);
+        assert!(emulator.is_on());
+        assert_eq!(emulator.multiplex(), 31);
+    }
+
+    #[test]
+    fn it_keeps_the_configuration_across_a_release() {
+        let mut emulator = Emulator::new();
+        let mut display = Display::new(&mut emulator, DisplayRotation::Rotate0);
+        display.init().unwrap();
+        display.on().unwrap();
+        display.set_pixel(0, 0, 0xF);
+        display.flush().unwrap();
+        let (_, registers) = display.into_parts();
+
+        // without the registers there is nothing to restore
+        let mut display = Display::new(&mut emulator, DisplayRotation::Rotate0);
+        assert!(matches!(display.refresh_config(), Err(crate::error::Error::NotInitialized)));
+
+        let mut display = Display::with_registers(&mut emulator, DisplayRotation::Rotate0, DisplayConfig::new(), registers);
+        display.set_pixel(0, 0, 0xF);
+        display.refresh_config().unwrap();
+
+        assert!(emulator.is_on());
+        assert_eq!(emulator.remap(), registers.remap());
+        assert_eq!(emulator.remap().to_byte(), 0x54);
+        assert_eq!(emulator.ram_pixel(0, 0), 0xF);
+        assert!(emulator.unknown_commands().is_empty());
+    }
+
+    #[test]
+    fn it_stops_a_scroll_started_before_a_release() {
+        let mut emulator = Emulator::new();
+        let mut display = Display::new(&mut emulator, DisplayRotation::Rotate0);
+        display.init().unwrap();
+        display.flush().unwrap();
+        display
+            .start_horizontal_scroll(ScrollDirection::Left, (0, 0), (8, 8), ScrollInterval::Frames2)
+            .unwrap();
+        let (_, registers) = display.into_parts();
+
+        let mut display = Display::with_registers(&mut emulator, DisplayRotation::Rotate0, DisplayConfig::new(), registers);
+        display.stop_horizontal_scroll().unwrap();
+        assert!(!display.registers().is_scrolling());
+        display.set_pixel(0, 0, 0xF);
+        display.flush().unwrap();
+
+        assert!(!emulator.is_scrolling());
+        assert_eq!(emulator.ram_pixel(0, 0), 0xF);
+    }
+
+    #[test]
+    fn it_keeps_the_framebuffer_in_sync_when_blanking() {
+        let mut emulator = Emulator::new();
+        let mut display = Display::new(&mut emulator, DisplayRotation::Rotate0);
+        display.init().unwrap();
+        display.set_pixel(100, 10, 0xF);
+        display.flush().unwrap();
+
+        display.blank().unwrap();
+        display.set_pixel(0, 0, 0xF);
+        display.flush().unwrap();
+
+        assert_eq!(display.get_pixel(100, 10), Some(0));
+        assert_eq!(emulator.ram_pixel(100, 10), 0);
+        assert_eq!(emulator.ram_pixel(0, 0), 0xF);
+    }
+
+    /// Interface that notes whether the panel was on while the display RAM was written
+    struct Probe<'a> {
+        emulator: &'a mut Emulator,
+        on_while_writing: bool,
+    }
+
+    impl WriteOnlyDataCommand for Probe<'_> {
+        fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+            self.emulator.send_commands(cmd)
+        }
+
+        fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+            self.on_while_writing |= self.emulator.is_on();
+            self.emulator.send_data(buf)
+        }
+    }
+
+    #[test]
+    fn it_recovers_a_controller_that_reset() {
+        let mut emulator = Emulator::new();
+        let mut display = Display::new(&mut emulator, DisplayRotation::Rotate0);
+        display.init().unwrap();
+        display.on().unwrap();
+        display.set_pixel(0, 0, 0xF);
+        display.flush().unwrap();
+        display
+            .start_horizontal_scroll(ScrollDirection::Left, (0, 0), (8, 8), ScrollInterval::Frames2)
+            .unwrap();
+        let (_, registers) = display.into_parts();
+
+        // a controller in its reset state, with garbage in the display RAM
+        let mut emulator = Emulator::new();
+        emulator.send_data(DataFormat::U8(&[0x55; 64])).unwrap();
+        let probe = Probe { emulator: &mut emulator, on_while_writing: false };
+        let mut display = Display::with_registers(probe, DisplayRotation::Rotate0, DisplayConfig::new(), registers);
+        display.set_pixel(0, 0, 0xF);
+        display.refresh_config().unwrap();
+
+        // the panel is only turned on once the RAM holds the image
+        assert!(!display.release().on_while_writing);
+        assert!(emulator.is_on());
+        assert!(emulator.is_scrolling());
+        assert_eq!(emulator.ram_pixel(0, 0), 0xF);
+        assert_eq!(emulator.ram_pixel(0, 1), 0);
+        assert!(emulator.unknown_commands().is_empty());
+    }
+}
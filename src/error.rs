@@ -1,10 +1,43 @@
+use core::convert::Infallible;
+use display_interface::DisplayError;
+
 /// Enum of errors in this crate
 ///
-/// Both error types `CommE` and `PinE` default to `()`
-#[derive(Debug)]
-pub enum Error<CommE, PinE> {
-    /// Communication error
-    Comm(CommE),
+/// The pin error type `PinE` is only used by `PowerControl`, which reports the `ErrorKind` of
+/// embedded-hal. It defaults to `Infallible`.
+#[derive(Debug, Clone)]
+pub enum Error<PinE = Infallible> {
+    /// Communication error of the display interface
+    Comm(DisplayError),
     /// Pin setting error
     Pin(PinE),
-}
\ No newline at end of file
+    /// Argument outside of the range the display accepts
+    InvalidArgument,
+    /// Data does not fit into a buffer
+    BufferOverflow,
+    /// The shadow registers don't hold the configuration of the display yet, see `Display::init`
+    NotInitialized,
+    /// The display RAM can't be written while a horizontal scroll is running,
+    /// see `Display::stop_horizontal_scroll`
+    Busy,
+}
+
+impl<PinE> From<DisplayError> for Error<PinE> {
+    fn from(error: DisplayError) -> Self {
+        Error::Comm(error)
+    }
+}
+
+impl Error {
+    /// Same error for an operation that can also fail on setting a pin
+    pub(crate) fn with_pin<PinE>(self) -> Error<PinE> {
+        match self {
+            Error::Comm(error) => Error::Comm(error),
+            Error::Pin(never) => match never {},
+            Error::InvalidArgument => Error::InvalidArgument,
+            Error::BufferOverflow => Error::BufferOverflow,
+            Error::NotInitialized => Error::NotInitialized,
+            Error::Busy => Error::Busy,
+        }
+    }
+}
@@ -0,0 +1,357 @@
+//! In-RAM copy of the display RAM that keeps track of the areas modified since the last upload
+
+/// Number of columns in the display RAM. Every column holds two pixels.
+pub const RAM_COLUMNS: usize = 128;
+
+/// Number of rows in the display RAM
+pub const RAM_ROWS: usize = 64;
+
+/// Maximum number of separate dirty areas. When more areas are modified they get merged.
+const MAX_DIRTY_AREAS: usize = 4;
+
+/// Dirty areas that are this close (in columns or rows) are merged into a single area,
+/// since sending a few extra bytes is cheaper than setting up another window.
+const MERGE_DISTANCE: u8 = 4;
+
+/// Rectangular window in the display RAM in (column, row) coordinates.
+/// The end is exclusive, just like in `Display::set_draw_area`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Area {
+    pub start: (u8, u8),
+    pub end: (u8, u8),
+}
+
+impl Area {
+    /// The complete display RAM
+    pub const FULL: Area = Area {
+        start: (0, 0),
+        end: (RAM_COLUMNS as u8, RAM_ROWS as u8),
+    };
+
+    /// Area of a single column and row
+    fn cell(col: u8, row: u8) -> Self {
+        Area {
+            start: (col, row),
+            end: (col + 1, row + 1),
+        }
+    }
+
+    /// Smallest area containing both areas
+    fn union(&self, other: &Area) -> Area {
+        Area {
+            start: (self.start.0.min(other.start.0), self.start.1.min(other.start.1)),
+            end: (self.end.0.max(other.end.0), self.end.1.max(other.end.1)),
+        }
+    }
+
+    /// True when the areas overlap or are less than `MERGE_DISTANCE` apart
+    fn is_near(&self, other: &Area) -> bool {
+        self.start.0 <= other.end.0.saturating_add(MERGE_DISTANCE)
+            && other.start.0 <= self.end.0.saturating_add(MERGE_DISTANCE)
+            && self.start.1 <= other.end.1.saturating_add(MERGE_DISTANCE)
+            && other.start.1 <= self.end.1.saturating_add(MERGE_DISTANCE)
+    }
+
+    /// Number of bytes in the area
+    fn len(&self) -> usize {
+        (self.end.0 - self.start.0) as usize * (self.end.1 - self.start.1) as usize
+    }
+}
+
+/// Upload of a dirty area in the order of the addressing mode, one chunk at a time.
+/// Holds the line buffer for horizontal addressing, so the blocking and the async
+/// `Display` only have to send the chunks.
+pub struct Upload {
+    area: Area,
+    vertical: bool,
+    index: usize,
+    line: [u8; RAM_COLUMNS],
+}
+
+impl Upload {
+    pub fn new(area: Area, vertical: bool) -> Self {
+        Upload {
+            area,
+            vertical,
+            index: 0,
+            line: [0; RAM_COLUMNS],
+        }
+    }
+
+    /// Area that is uploaded
+    pub fn area(&self) -> Area {
+        self.area
+    }
+
+    /// Next bytes to send: a column, or the whole area when it spans the full height, in
+    /// vertical addressing mode, a row in horizontal addressing mode. `None` when done.
+    pub fn next_chunk<'a>(&'a mut self, framebuffer: &'a FrameBuffer) -> Option<&'a [u8]> {
+        let index = self.index;
+        self.index += 1;
+
+        if self.vertical {
+            framebuffer.area_data(self.area).nth(index)
+        } else {
+            let row = self.area.start.1 as usize + index;
+            if row < self.area.end.1 as usize {
+                Some(framebuffer.row_data(self.area, row as u8, &mut self.line))
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// 4-bit grayscale framebuffer, two pixels per byte with the left pixel in the high nibble.
+/// Stored column by column [col0: row0 row1 ... row63, col1: row0 ...] to match the
+/// vertical addressing mode set up in `Display::init`.
+pub struct FrameBuffer {
+    buffer: [u8; RAM_COLUMNS * RAM_ROWS],
+    dirty: [Option<Area>; MAX_DIRTY_AREAS],
+}
+
+impl FrameBuffer {
+    /// Create an empty framebuffer. Everything is dirty, since the contents of the
+    /// display RAM are unknown.
+    pub fn new() -> Self {
+        let mut dirty = [None; MAX_DIRTY_AREAS];
+        dirty[0] = Some(Area::FULL);
+
+        FrameBuffer {
+            buffer: [0; RAM_COLUMNS * RAM_ROWS],
+            dirty,
+        }
+    }
+
+    /// Set a pixel in display RAM coordinates to a gray level (0-15).
+    /// Pixels outside of the display RAM are ignored.
+    /// Only marks the pixel dirty when its level actually changes.
+    pub fn set_pixel(&mut self, x: usize, y: usize, level: u8) {
+        if let Some((index, shift)) = Self::pixel_index(x, y) {
+            let byte = self.buffer[index];
+            let new = (byte & !(0x0F << shift)) | ((level & 0x0F) << shift);
+
+            if new != byte {
+                self.buffer[index] = new;
+                self.mark_dirty(Area::cell((x / 2) as u8, y as u8));
+            }
+        }
+    }
+
+    /// Get the gray level of a pixel in display RAM coordinates
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<u8> {
+        Self::pixel_index(x, y).map(|(index, shift)| (self.buffer[index] >> shift) & 0x0F)
+    }
+
+    /// Fill the complete framebuffer with a single gray level
+    pub fn fill(&mut self, level: u8) {
+        let level = level & 0x0F;
+        let byte = (level << 4) | level;
+
+        if self.buffer.iter().any(|b| *b != byte) {
+            for b in self.buffer.iter_mut() {
+                *b = byte;
+            }
+            self.mark_dirty(Area::FULL);
+        }
+    }
+
+    /// Record that an area has to be uploaded on the next flush
+    pub fn mark_dirty(&mut self, area: Area) {
+        let mut area = area;
+
+        loop {
+            // absorb every area that is near, the union might then reach further areas
+            while let Some(near) = self.dirty.iter_mut().find(|a| matches!(a, Some(a) if a.is_near(&area))) {
+                area = area.union(&near.take().unwrap());
+            }
+
+            if let Some(free) = self.dirty.iter_mut().find(|a| a.is_none()) {
+                *free = Some(area);
+                return;
+            }
+
+            // out of slots, merge with the area that grows the least and try again
+            let cheapest = self.dirty
+                .iter_mut()
+                .min_by_key(|a| a.map_or(0, |a| a.union(&area).len() - a.len()))
+                .unwrap();
+            area = area.union(&cheapest.take().unwrap());
+        }
+    }
+
+    /// Next area that has to be uploaded, if any
+    pub fn next_dirty(&self) -> Option<Area> {
+        self.dirty.iter().flatten().next().copied()
+    }
+
+    /// Mark an area returned by `next_dirty` as uploaded
+    pub fn clean(&mut self, area: Area) {
+        for a in self.dirty.iter_mut() {
+            if *a == Some(area) {
+                *a = None;
+            }
+        }
+    }
+
+    /// The bytes of an area, in the order the display expects them in vertical addressing mode.
+    /// Areas spanning the full height are returned as one slice, others as one slice per column.
+    pub fn area_data(&self, area: Area) -> impl Iterator<Item = &[u8]> {
+        let (rows_start, rows_end) = (area.start.1 as usize, area.end.1 as usize);
+        let columns = &self.buffer[area.start.0 as usize * RAM_ROWS..area.end.0 as usize * RAM_ROWS];
+
+        let full_height = rows_start == 0 && rows_end == RAM_ROWS;
+        let chunk = if full_height { columns.len() } else { RAM_ROWS };
+
+        columns
+            .chunks(chunk)
+            .map(move |c| if full_height { c } else { &c[rows_start..rows_end] })
+    }
+
+    /// One row of an area, for horizontal addressing mode. Copied into `line` since
+    /// the bytes of a row are not next to each other in the buffer.
+    pub fn row_data<'a>(&self, area: Area, row: u8, line: &'a mut [u8; RAM_COLUMNS]) -> &'a [u8] {
+        let columns = area.start.0 as usize..area.end.0 as usize;
+        let len = columns.len();
+
+        for (byte, col) in line.iter_mut().zip(columns) {
+            *byte = self.buffer[col * RAM_ROWS + row as usize];
+        }
+        &line[..len]
+    }
+
+    /// Byte index in the buffer and nibble shift of a pixel
+    fn pixel_index(x: usize, y: usize) -> Option<(usize, u8)> {
+        if x >= RAM_COLUMNS * 2 || y >= RAM_ROWS {
+            return None;
+        }
+
+        let shift = if x & 1 == 0 { 4 } else { 0 };
+        Some(((x / 2) * RAM_ROWS + y, shift))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    fn clean_buffer() -> FrameBuffer {
+        let mut fb = FrameBuffer::new();
+        fb.clean(Area::FULL);
+        fb
+    }
+
+    fn dirty_areas(fb: &FrameBuffer) -> Vec<Area> {
+        fb.dirty.iter().flatten().copied().collect()
+    }
+
+    #[test]
+    fn it_packs_pixels_column_by_column() {
+        let mut fb = clean_buffer();
+
+        fb.set_pixel(0, 0, 0x3);
+        fb.set_pixel(1, 0, 0xA);
+        fb.set_pixel(255, 63, 0xF);
+
+        // left pixel in the high nibble, columns stored one after the other
+        assert_eq!(fb.buffer[0], 0x3A);
+        assert_eq!(fb.buffer[127 * 64 + 63], 0x0F);
+
+        // overwriting only touches a single nibble
+        fb.set_pixel(0, 0, 0x0);
+        assert_eq!(fb.buffer[0], 0x0A);
+    }
+
+    #[test]
+    fn it_starts_fully_dirty() {
+        let fb = FrameBuffer::new();
+        assert_eq!(fb.next_dirty(), Some(Area::FULL));
+    }
+
+    #[test]
+    fn it_only_marks_changed_pixels() {
+        let mut fb = clean_buffer();
+
+        fb.set_pixel(10, 10, 0);
+        fb.fill(0);
+        assert_eq!(fb.next_dirty(), None);
+
+        fb.set_pixel(11, 10, 5);
+        assert_eq!(dirty_areas(&fb), [Area { start: (5, 10), end: (6, 11) }]);
+    }
+
+    #[test]
+    fn it_merges_nearby_areas() {
+        let mut fb = clean_buffer();
+
+        fb.set_pixel(0, 0, 1);
+        fb.set_pixel(4, 2, 1);
+        fb.set_pixel(200, 60, 1);
+
+        assert_eq!(
+            dirty_areas(&fb),
+            [
+                Area { start: (0, 0), end: (3, 3) },
+                Area { start: (100, 60), end: (101, 61) }
+            ]
+        );
+    }
+
+    #[test]
+    fn it_merges_when_out_of_slots() {
+        let mut fb = clean_buffer();
+
+        for i in 0..MAX_DIRTY_AREAS + 1 {
+            fb.set_pixel(i * 40, i * 10, 1);
+        }
+
+        let areas = dirty_areas(&fb);
+        assert_eq!(areas.len(), MAX_DIRTY_AREAS);
+
+        // every pixel is still covered by one of the areas
+        for i in 0..MAX_DIRTY_AREAS + 1 {
+            let (col, row) = ((i * 20) as u8, (i * 10) as u8);
+            assert!(areas.iter().any(|a| a.start.0 <= col
+                && col < a.end.0
+                && a.start.1 <= row
+                && row < a.end.1));
+        }
+    }
+
+    #[test]
+    fn it_returns_area_data_per_column() {
+        let mut fb = clean_buffer();
+        fb.set_pixel(2, 1, 0xC);
+        fb.set_pixel(4, 2, 0x3);
+
+        let area = Area { start: (1, 1), end: (3, 3) };
+        let data: Vec<&[u8]> = fb.area_data(area).collect();
+        assert_eq!(data, [&[0xC0, 0x00][..], &[0x00, 0x30][..]]);
+
+        let data: Vec<&[u8]> = fb.area_data(Area::FULL).collect();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].len(), RAM_COLUMNS * RAM_ROWS);
+    }
+
+    #[test]
+    fn it_uploads_in_the_order_of_the_addressing_mode() {
+        let mut fb = clean_buffer();
+        fb.set_pixel(2, 1, 0xC);
+        fb.set_pixel(2, 2, 0x5);
+        fb.set_pixel(4, 2, 0x3);
+        let area = Area { start: (1, 1), end: (3, 3) };
+
+        // column by column
+        let mut upload = Upload::new(area, true);
+        assert_eq!(upload.next_chunk(&fb), Some(&[0xC0, 0x50][..]));
+        assert_eq!(upload.next_chunk(&fb), Some(&[0x00, 0x30][..]));
+        assert_eq!(upload.next_chunk(&fb), None);
+
+        // row by row
+        let mut upload = Upload::new(area, false);
+        assert_eq!(upload.next_chunk(&fb), Some(&[0xC0, 0x00][..]));
+        assert_eq!(upload.next_chunk(&fb), Some(&[0x50, 0x30][..]));
+        assert_eq!(upload.next_chunk(&fb), None);
+    }
+}
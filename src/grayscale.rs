@@ -0,0 +1,83 @@
+//! Gray scale lookup tables
+//!
+//! The SSD1362 drives every gray level with a PWM pulse. The gray scale table sets the pulse width
+//! (in display clocks) of the levels GS1 - GS15, GS0 is always off.
+
+use crate::error::Error;
+
+/// Largest pulse width accepted in a gray scale table
+pub const MAX_PULSE_WIDTH: u8 = 0x3F;
+
+/// Validated gray scale table with the pulse widths of gray levels GS1 - GS15
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GrayscaleTable {
+    widths: [u8; 15],
+}
+
+impl GrayscaleTable {
+    /// Equal steps between all levels, close to the default table of the display
+    pub const LINEAR: GrayscaleTable = GrayscaleTable {
+        widths: [4, 8, 12, 16, 20, 24, 28, 32, 36, 40, 44, 48, 52, 56, 60],
+    };
+
+    /// Gamma 1.8 curve
+    pub const GAMMA_1_8: GrayscaleTable = GrayscaleTable {
+        widths: [1, 3, 4, 7, 10, 13, 17, 21, 26, 31, 36, 42, 49, 56, 63],
+    };
+
+    /// Gamma 2.2 curve, perceptually even steps for sRGB-like content
+    pub const GAMMA_2_2: GrayscaleTable = GrayscaleTable {
+        widths: [1, 2, 3, 4, 7, 9, 13, 17, 21, 26, 32, 39, 46, 54, 63],
+    };
+
+    /// Inverse gamma 2.2 curve, raises the low levels.
+    /// For panels where the darkest levels are hard to tell apart with the linear table.
+    pub const GAMMA_0_45: GrayscaleTable = GrayscaleTable {
+        widths: [19, 26, 31, 35, 39, 42, 45, 48, 50, 53, 55, 57, 59, 61, 63],
+    };
+
+    /// Create a table from the pulse widths of GS1 - GS15.
+    /// Returns `Error::InvalidArgument` unless the widths are strictly increasing and
+    /// at most `MAX_PULSE_WIDTH`.
+    pub fn new(widths: [u8; 15]) -> Result<Self, Error> {
+        let increasing = widths.windows(2).all(|pair| pair[0] < pair[1]);
+        if !increasing || widths[14] > MAX_PULSE_WIDTH {
+            return Err(Error::InvalidArgument);
+        }
+
+        Ok(GrayscaleTable { widths })
+    }
+
+    /// Pulse widths of GS1 - GS15
+    pub fn widths(&self) -> [u8; 15] {
+        self.widths
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_accepts_the_presets() {
+        for table in &[
+            GrayscaleTable::LINEAR,
+            GrayscaleTable::GAMMA_1_8,
+            GrayscaleTable::GAMMA_2_2,
+            GrayscaleTable::GAMMA_0_45,
+        ] {
+            assert_eq!(GrayscaleTable::new(table.widths()).ok(), Some(*table));
+        }
+    }
+
+    #[test]
+    fn it_rejects_invalid_tables() {
+        let mut widths = GrayscaleTable::LINEAR.widths();
+        widths[5] = widths[4];
+        assert!(matches!(GrayscaleTable::new(widths), Err(Error::InvalidArgument)));
+
+        let mut widths = GrayscaleTable::LINEAR.widths();
+        widths[14] = MAX_PULSE_WIDTH + 1;
+        assert!(matches!(GrayscaleTable::new(widths), Err(Error::InvalidArgument)));
+    }
+}
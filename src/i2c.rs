@@ -0,0 +1,157 @@
+//! I2C interface for displays with the bus mode pins set to I2C
+//!
+//! Every transfer starts with a control byte that tells the controller whether the
+//! following bytes are commands or display RAM data, there is no D/C pin.
+//!
+//! ```ignore
+//! let interface = I2cInterface::new(i2c, DEFAULT_ADDRESS);
+//! let mut display = Display::new(interface, DisplayRotation::Rotate0);
+//! display.init()?;
+//! ```
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_hal::i2c::I2c;
+
+/// Control byte for a stream of commands (Co = 0, D/C# = 0)
+const CONTROL_COMMANDS: u8 = 0x00;
+
+/// Control byte for a stream of display RAM data (Co = 0, D/C# = 1)
+const CONTROL_DATA: u8 = 0x40;
+
+/// Slave address with the SA0 pin low
+pub const DEFAULT_ADDRESS: u8 = 0x3C;
+
+/// Slave address with the SA0 pin high
+pub const ALTERNATE_ADDRESS: u8 = 0x3D;
+
+/// Bytes sent per transfer after the control byte. Small enough for the transfer limits
+/// of common I2C peripherals, large enough to keep the overhead of the address and control
+/// byte low. A full frame is 256 transfers.
+pub const CHUNK_LEN: usize = 32;
+
+/// `WriteOnlyDataCommand` for the SSD1362 on an I2C bus.
+/// Large buffers are split into transfers of at most `CHUNK_LEN` bytes.
+pub struct I2cInterface<I2C> {
+    i2c: I2C,
+    address: u8,
+}
+
+impl<I2C> I2cInterface<I2C> {
+    /// Talk to the display at a 7-bit slave address
+    pub fn new(i2c: I2C, address: u8) -> Self {
+        I2cInterface { i2c, address }
+    }
+
+    /// Give back the I2C bus
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C: I2c> I2cInterface<I2C> {
+    /// Send the bytes in transfers of at most `CHUNK_LEN` bytes, each prefixed with the control byte
+    fn send(&mut self, control: u8, format: DataFormat<'_>) -> Result<(), DisplayError> {
+        let mut chunk = [0u8; CHUNK_LEN + 1];
+        chunk[0] = control;
+
+        match format {
+            DataFormat::U8(bytes) => {
+                for part in bytes.chunks(CHUNK_LEN) {
+                    chunk[1..=part.len()].copy_from_slice(part);
+                    self.write(&chunk[..=part.len()])?;
+                }
+            }
+            DataFormat::U8Iter(iter) => {
+                let mut len = 0;
+                for byte in iter {
+                    len += 1;
+                    chunk[len] = byte;
+                    if len == CHUNK_LEN {
+                        self.write(&chunk)?;
+                        len = 0;
+                    }
+                }
+                if len > 0 {
+                    self.write(&chunk[..=len])?;
+                }
+            }
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), DisplayError> {
+        self.i2c.write(self.address, bytes).map_err(|_| DisplayError::BusWriteError)
+    }
+}
+
+impl<I2C: I2c> WriteOnlyDataCommand for I2cInterface<I2C> {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.send(CONTROL_COMMANDS, cmd)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.send(CONTROL_DATA, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::{Display, DisplayRotation};
+    use embedded_hal::i2c::{ErrorKind, ErrorType, Operation};
+    use std::vec::Vec;
+
+    /// I2C bus that records every write transfer
+    #[derive(Default)]
+    struct Bus {
+        transfers: Vec<(u8, Vec<u8>)>,
+    }
+
+    impl ErrorType for Bus {
+        type Error = ErrorKind;
+    }
+
+    impl I2c for Bus {
+        fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+            for operation in operations {
+                match operation {
+                    Operation::Write(bytes) => self.transfers.push((address, bytes.to_vec())),
+                    Operation::Read(_) => return Err(ErrorKind::Other),
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn it_prefixes_the_control_byte() {
+        let interface = I2cInterface::new(Bus::default(), ALTERNATE_ADDRESS);
+        let mut display = Display::new(interface, DisplayRotation::Rotate0);
+
+        display.on().unwrap();
+        display.draw(&[0x12, 0x34]).unwrap();
+
+        assert_eq!(
+            display.iface.i2c.transfers,
+            [(0x3D, vec![0x00, 0xAF]), (0x3D, vec![0x40, 0x12, 0x34])]
+        );
+    }
+
+    #[test]
+    fn it_splits_large_buffers_into_chunks() {
+        let mut interface = I2cInterface::new(Bus::default(), DEFAULT_ADDRESS);
+        let data: Vec<u8> = (0..CHUNK_LEN as u8 * 2 + 3).collect();
+
+        interface.send_data(DataFormat::U8(&data)).unwrap();
+        interface.send_data(DataFormat::U8Iter(&mut data.iter().copied())).unwrap();
+
+        let transfers = interface.release().transfers;
+        let lengths: Vec<usize> = transfers.iter().map(|(_, bytes)| bytes.len()).collect();
+        assert_eq!(lengths, [CHUNK_LEN + 1, CHUNK_LEN + 1, 4, CHUNK_LEN + 1, CHUNK_LEN + 1, 4]);
+
+        let sent: Vec<u8> = transfers[..3].iter().flat_map(|(_, bytes)| bytes[1..].to_vec()).collect();
+        assert_eq!(sent, data);
+        assert!(transfers.iter().all(|(address, bytes)| *address == 0x3C && bytes[0] == 0x40));
+    }
+}
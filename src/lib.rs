@@ -1,10 +1,27 @@
 #![no_std]
 
+#[cfg(any(test, feature = "std"))]
+#[macro_use]
+extern crate std;
+
 extern crate embedded_hal;
 
-mod command;
+#[cfg(feature = "async")]
+pub mod asynch;
+pub mod command;
+#[cfg(feature = "compat")]
+pub mod compat;
+pub mod config;
+#[cfg(feature = "std")]
+pub mod emulator;
 pub mod error;
 pub mod display;
+mod framebuffer;
+pub mod grayscale;
+pub mod i2c;
+pub mod parallel;
+pub mod power;
+pub mod registers;
 
 pub mod terminal;
 pub use terminal::chars::Font6x8;
\ No newline at end of file
@@ -0,0 +1,452 @@
+//! 8-bit parallel interfaces in 8080 and 6800 mode, bit-banged over GPIO pins
+//!
+//! `Display` works with any parallel `WriteOnlyDataCommand`, these ones only need output pins.
+//! D/C selects commands (low) or data (high). In 8080 mode every byte is latched on the rising
+//! edge of WR#, RD# is tied high. In 6800 mode R/W# is held low and every byte is latched on the
+//! falling edge of E. CS# is driven low for every transfer when a pin is given with `with_cs`,
+//! otherwise it has to be tied low.
+//!
+//! ```ignore
+//! let bus = GpioBus::new([d0, d1, d2, d3, d4, d5, d6, d7]);
+//! let interface = Parallel8080Interface::new(bus, dc, wr);
+//! let mut display = Display::new(interface, DisplayRotation::Rotate0);
+//! display.init()?;
+//! ```
+//!
+//! The SSD1362 parallel interface is always 8 bits wide, there is no 9, 16 or 18 bit mode to
+//! configure, so `OutputBus` takes a `u8`. To set all data lines with a single port write instead
+//! of one pin at a time, implement `OutputBus` for the GPIO port.
+
+use core::convert::Infallible;
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_hal::digital::{ErrorType, OutputPin};
+
+/// Data lines D0 - D7 of the parallel interface
+pub trait OutputBus {
+    type Error: core::fmt::Debug;
+
+    /// Drive the data lines to a byte, D0 is the least significant bit
+    fn set_value(&mut self, value: u8) -> Result<(), Self::Error>;
+}
+
+/// Data lines on eight separate GPIO pins, `pins[0]` is D0.
+/// Only the pins that change are written. HALs usually have a type-erased pin
+/// (e.g. `degrade()` or `into_erased()`) to put pins of different ports into one array.
+pub struct GpioBus<P> {
+    pins: [P; 8],
+    value: Option<u8>,
+}
+
+impl<P: OutputPin> GpioBus<P> {
+    pub fn new(pins: [P; 8]) -> Self {
+        GpioBus { pins, value: None }
+    }
+
+    /// Give back the pins
+    pub fn release(self) -> [P; 8] {
+        self.pins
+    }
+}
+
+impl<P: OutputPin> OutputBus for GpioBus<P> {
+    type Error = P::Error;
+
+    fn set_value(&mut self, value: u8) -> Result<(), Self::Error> {
+        // all pins are unknown before the first write
+        let changed = self.value.map_or(0xFF, |last| last ^ value);
+
+        for (bit, pin) in self.pins.iter_mut().enumerate() {
+            if changed & (1 << bit) != 0 {
+                if value & (1 << bit) != 0 {
+                    pin.set_high()?;
+                } else {
+                    pin.set_low()?;
+                }
+            }
+        }
+        self.value = Some(value);
+        Ok(())
+    }
+}
+
+/// CS# tied low, for interfaces without a chip select pin
+pub struct NoCs;
+
+impl ErrorType for NoCs {
+    type Error = Infallible;
+}
+
+impl OutputPin for NoCs {
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// Write the bytes of a command or data transfer one at a time
+fn for_each_byte(
+    format: DataFormat<'_>,
+    mut write: impl FnMut(u8) -> Result<(), DisplayError>,
+) -> Result<(), DisplayError> {
+    match format {
+        DataFormat::U8(bytes) => {
+            for byte in bytes {
+                write(*byte)?;
+            }
+        }
+        DataFormat::U8Iter(iter) => {
+            for byte in iter {
+                write(byte)?;
+            }
+        }
+        _ => return Err(DisplayError::DataFormatNotImplemented),
+    }
+    Ok(())
+}
+
+/// `WriteOnlyDataCommand` for the 8080 parallel interface
+pub struct Parallel8080Interface<BUS, DC, WR, CS = NoCs> {
+    bus: BUS,
+    dc: DC,
+    wr: WR,
+    cs: CS,
+}
+
+impl<BUS, DC, WR> Parallel8080Interface<BUS, DC, WR>
+where
+    BUS: OutputBus,
+    DC: OutputPin,
+    WR: OutputPin,
+{
+    pub fn new(bus: BUS, dc: DC, wr: WR) -> Self {
+        Parallel8080Interface { bus, dc, wr, cs: NoCs }
+    }
+
+    /// Give back the data bus and the D/C and WR# pins
+    pub fn release(self) -> (BUS, DC, WR) {
+        (self.bus, self.dc, self.wr)
+    }
+}
+
+impl<BUS, DC, WR, CS> Parallel8080Interface<BUS, DC, WR, CS>
+where
+    BUS: OutputBus,
+    DC: OutputPin,
+    WR: OutputPin,
+    CS: OutputPin,
+{
+    /// Interface that drives CS# low for every transfer and high again after it
+    pub fn with_cs(bus: BUS, dc: DC, wr: WR, cs: CS) -> Self {
+        Parallel8080Interface { bus, dc, wr, cs }
+    }
+
+    /// Give back the data bus and the D/C, WR# and CS# pins
+    pub fn into_parts(self) -> (BUS, DC, WR, CS) {
+        (self.bus, self.dc, self.wr, self.cs)
+    }
+
+    /// Select the chip, set D/C and write the bytes
+    fn send(&mut self, dc: bool, format: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.cs.set_low().map_err(|_| DisplayError::CSError)?;
+        self.dc.set_state(dc.into()).map_err(|_| DisplayError::DCError)?;
+        let Self { bus, wr, .. } = self;
+        // put a byte on the bus and latch it with a WR# pulse
+        for_each_byte(format, |byte| {
+            wr.set_low().map_err(|_| DisplayError::BusWriteError)?;
+            bus.set_value(byte).map_err(|_| DisplayError::BusWriteError)?;
+            wr.set_high().map_err(|_| DisplayError::BusWriteError)
+        })?;
+        self.cs.set_high().map_err(|_| DisplayError::CSError)
+    }
+}
+
+impl<BUS, DC, WR, CS> WriteOnlyDataCommand for Parallel8080Interface<BUS, DC, WR, CS>
+where
+    BUS: OutputBus,
+    DC: OutputPin,
+    WR: OutputPin,
+    CS: OutputPin,
+{
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.send(false, cmd)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.send(true, buf)
+    }
+}
+
+/// `WriteOnlyDataCommand` for the 6800 parallel interface
+pub struct Parallel6800Interface<BUS, DC, RW, E, CS = NoCs> {
+    bus: BUS,
+    dc: DC,
+    rw: RW,
+    e: E,
+    cs: CS,
+}
+
+impl<BUS, DC, RW, E> Parallel6800Interface<BUS, DC, RW, E>
+where
+    BUS: OutputBus,
+    DC: OutputPin,
+    RW: OutputPin,
+    E: OutputPin,
+{
+    pub fn new(bus: BUS, dc: DC, rw: RW, e: E) -> Self {
+        Parallel6800Interface { bus, dc, rw, e, cs: NoCs }
+    }
+
+    /// Give back the data bus and the D/C, R/W# and E pins
+    pub fn release(self) -> (BUS, DC, RW, E) {
+        (self.bus, self.dc, self.rw, self.e)
+    }
+}
+
+impl<BUS, DC, RW, E, CS> Parallel6800Interface<BUS, DC, RW, E, CS>
+where
+    BUS: OutputBus,
+    DC: OutputPin,
+    RW: OutputPin,
+    E: OutputPin,
+    CS: OutputPin,
+{
+    /// Interface that drives CS# low for every transfer and high again after it
+    pub fn with_cs(bus: BUS, dc: DC, rw: RW, e: E, cs: CS) -> Self {
+        Parallel6800Interface { bus, dc, rw, e, cs }
+    }
+
+    /// Give back the data bus and the D/C, R/W#, E and CS# pins
+    pub fn into_parts(self) -> (BUS, DC, RW, E, CS) {
+        (self.bus, self.dc, self.rw, self.e, self.cs)
+    }
+
+    /// Select the chip, set D/C and R/W# and write the bytes
+    fn send(&mut self, dc: bool, format: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.cs.set_low().map_err(|_| DisplayError::CSError)?;
+        self.dc.set_state(dc.into()).map_err(|_| DisplayError::DCError)?;
+        self.rw.set_low().map_err(|_| DisplayError::BusWriteError)?;
+        let Self { bus, e, .. } = self;
+        // put a byte on the bus and latch it with an E pulse
+        for_each_byte(format, |byte| {
+            bus.set_value(byte).map_err(|_| DisplayError::BusWriteError)?;
+            e.set_high().map_err(|_| DisplayError::BusWriteError)?;
+            e.set_low().map_err(|_| DisplayError::BusWriteError)
+        })?;
+        self.cs.set_high().map_err(|_| DisplayError::CSError)
+    }
+}
+
+impl<BUS, DC, RW, E, CS> WriteOnlyDataCommand for Parallel6800Interface<BUS, DC, RW, E, CS>
+where
+    BUS: OutputBus,
+    DC: OutputPin,
+    RW: OutputPin,
+    E: OutputPin,
+    CS: OutputPin,
+{
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.send(false, cmd)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.send(true, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::{Display, DisplayRotation};
+    use core::cell::RefCell;
+    use std::rc::Rc;
+    use std::vec::Vec;
+
+    /// Levels of the lines, the edges of the control lines and the (D/C, byte) pairs latched by
+    /// WR# or E
+    #[derive(Default)]
+    struct Lines {
+        data: [bool; 8],
+        dc: bool,
+        wr: bool,
+        e: bool,
+        rw: bool,
+        cs: bool,
+        writes: usize,
+        edges: Vec<(Line, bool)>,
+        latched: Vec<(bool, u8)>,
+    }
+
+    impl Lines {
+        fn latch(&mut self) {
+            assert!(!self.cs, "latched while CS# is high");
+            assert!(!self.rw, "latched while R/W# is high");
+            let byte = (0..8).filter(|bit| self.data[*bit]).map(|bit| 1 << bit).sum();
+            let dc = self.dc;
+            self.latched.push((dc, byte));
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Line {
+        Data(usize),
+        DC,
+        WR,
+        E,
+        RW,
+        CS,
+    }
+
+    struct Pin(Line, Rc<RefCell<Lines>>);
+
+    impl ErrorType for Pin {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for Pin {
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            let mut lines = self.1.borrow_mut();
+            match self.0 {
+                Line::Data(bit) => lines.data[bit] = false,
+                Line::DC => lines.dc = false,
+                Line::WR => lines.wr = false,
+                Line::E => {
+                    if lines.e {
+                        lines.latch();
+                    }
+                    lines.e = false;
+                }
+                Line::RW => lines.rw = false,
+                Line::CS => lines.cs = false,
+            }
+            if !matches!(self.0, Line::Data(_)) {
+                lines.edges.push((self.0, false));
+            }
+            lines.writes += 1;
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            let mut lines = self.1.borrow_mut();
+            match self.0 {
+                Line::Data(bit) => lines.data[bit] = true,
+                Line::DC => lines.dc = true,
+                Line::WR => {
+                    if !lines.wr {
+                        lines.latch();
+                    }
+                    lines.wr = true;
+                }
+                Line::E => lines.e = true,
+                Line::RW => lines.rw = true,
+                Line::CS => lines.cs = true,
+            }
+            if !matches!(self.0, Line::Data(_)) {
+                lines.edges.push((self.0, true));
+            }
+            lines.writes += 1;
+            Ok(())
+        }
+    }
+
+    fn bus(lines: &Rc<RefCell<Lines>>) -> GpioBus<Pin> {
+        GpioBus::new([0, 1, 2, 3, 4, 5, 6, 7].map(|bit| Pin(Line::Data(bit), lines.clone())))
+    }
+
+    fn interface(lines: &Rc<RefCell<Lines>>) -> Parallel8080Interface<GpioBus<Pin>, Pin, Pin> {
+        Parallel8080Interface::new(bus(lines), Pin(Line::DC, lines.clone()), Pin(Line::WR, lines.clone()))
+    }
+
+    fn interface_6800(lines: &Rc<RefCell<Lines>>) -> Parallel6800Interface<GpioBus<Pin>, Pin, Pin, Pin, Pin> {
+        let pin = |line| Pin(line, lines.clone());
+        Parallel6800Interface::with_cs(bus(lines), pin(Line::DC), pin(Line::RW), pin(Line::E), pin(Line::CS))
+    }
+
+    #[test]
+    fn it_latches_commands_and_data() {
+        let lines = Rc::new(RefCell::new(Lines::default()));
+        let mut display = Display::new(interface(&lines), DisplayRotation::Rotate0);
+
+        display.set_draw_area((0, 0), (2, 1)).unwrap();
+        display.draw(&[0xA5, 0x5A]).unwrap();
+
+        assert_eq!(
+            lines.borrow().latched,
+            [(false, 0x15), (false, 0x00), (false, 0x01), (false, 0x75), (false, 0x00), (false, 0x00),
+             (true, 0xA5), (true, 0x5A)]
+        );
+        assert!(lines.borrow().wr);
+    }
+
+    #[test]
+    fn it_only_writes_the_pins_that_change() {
+        let lines = Rc::new(RefCell::new(Lines::default()));
+        let mut interface = interface(&lines);
+
+        interface.send_data(DataFormat::U8(&[0x00])).unwrap();
+        let writes = lines.borrow().writes;
+
+        // D/C and two WR# edges per byte, the bus stays the same
+        interface.send_data(DataFormat::U8Iter(&mut [0x00, 0x00].iter().copied())).unwrap();
+        assert_eq!(lines.borrow().writes - writes, 1 + 2 * 2);
+
+        // one more for the data line that changes
+        interface.send_data(DataFormat::U8(&[0x01])).unwrap();
+        assert_eq!(lines.borrow().writes - writes, 5 + 1 + 3);
+        assert_eq!(lines.borrow().latched.last(), Some(&(true, 0x01)));
+    }
+
+    #[test]
+    fn it_strobes_wr_while_selected_in_8080_mode() {
+        let lines = Rc::new(RefCell::new(Lines { cs: true, ..Lines::default() }));
+        let pin = |line| Pin(line, lines.clone());
+        let mut interface = Parallel8080Interface::with_cs(bus(&lines), pin(Line::DC), pin(Line::WR), pin(Line::CS));
+
+        interface.send_commands(DataFormat::U8(&[0xAF, 0xA4])).unwrap();
+
+        assert_eq!(
+            lines.borrow().edges,
+            [(Line::CS, false), (Line::DC, false),
+             (Line::WR, false), (Line::WR, true),
+             (Line::WR, false), (Line::WR, true),
+             (Line::CS, true)]
+        );
+        assert_eq!(lines.borrow().latched, [(false, 0xAF), (false, 0xA4)]);
+    }
+
+    #[test]
+    fn it_strobes_e_while_selected_in_6800_mode() {
+        let lines = Rc::new(RefCell::new(Lines { cs: true, rw: true, ..Lines::default() }));
+        let mut interface = interface_6800(&lines);
+
+        interface.send_data(DataFormat::U8(&[0x12, 0x34])).unwrap();
+
+        assert_eq!(
+            lines.borrow().edges,
+            [(Line::CS, false), (Line::DC, true), (Line::RW, false),
+             (Line::E, true), (Line::E, false),
+             (Line::E, true), (Line::E, false),
+             (Line::CS, true)]
+        );
+        assert_eq!(lines.borrow().latched, [(true, 0x12), (true, 0x34)]);
+    }
+
+    #[test]
+    fn it_drives_the_display_in_6800_mode() {
+        let lines = Rc::new(RefCell::new(Lines::default()));
+        let mut display = Display::new(interface_6800(&lines), DisplayRotation::Rotate0);
+
+        display.set_draw_area((0, 0), (2, 1)).unwrap();
+        display.draw(&[0xA5, 0x5A]).unwrap();
+
+        assert_eq!(
+            lines.borrow().latched,
+            [(false, 0x15), (false, 0x00), (false, 0x01), (false, 0x75), (false, 0x00), (false, 0x00),
+             (true, 0xA5), (true, 0x5A)]
+        );
+        assert!(!lines.borrow().e);
+        assert!(lines.borrow().cs);
+    }
+}
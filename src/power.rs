@@ -0,0 +1,201 @@
+//! Reset and power sequencing
+//!
+//! The panel can be damaged when the high voltage VCC supply is on while the controller is not
+//! initialised. `PowerControl` takes the reset pin and the pin enabling VCC (e.g. a 16V boost
+//! converter) and runs the power on and off sequences from the datasheet.
+//! VCI, the logic supply, is expected to be on whenever the MCU runs.
+//!
+//! The two pins may be of different types, e.g. one of them behind an I/O expander. Their
+//! errors are reported as the `ErrorKind` of embedded-hal.
+
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{Error as _, ErrorKind, OutputPin};
+
+use crate::display::Display;
+use crate::error::Error;
+
+/// Wait after VCI is on, and length of the reset pulse
+const RESET_DELAY_MS: u32 = 1;
+
+/// Time for VCC to become stable before the display is turned on
+const VCC_ON_DELAY_MS: u32 = 100;
+
+/// Time for the panel to discharge after turning off VCC
+const VCC_OFF_DELAY_MS: u32 = 100;
+
+pub struct PowerControl<RST, VCC> {
+    rst: RST,
+    vcc_en: VCC,
+}
+
+impl<RST, VCC> PowerControl<RST, VCC>
+where
+    RST: OutputPin,
+    VCC: OutputPin,
+{
+    /// Create a power controller from the reset pin (active low) and the VCC enable pin (active high)
+    pub fn new(rst: RST, vcc_en: VCC) -> Self {
+        PowerControl { rst, vcc_en }
+    }
+
+    /// Pulse the reset pin. Makes sure VCC is off first.
+    pub fn reset<D>(&mut self, delay: &mut D) -> Result<(), ErrorKind>
+    where
+        D: DelayNs,
+    {
+        self.vcc_en.set_low().map_err(|e| e.kind())?;
+        self.rst.set_high().map_err(|e| e.kind())?;
+        delay.delay_ms(RESET_DELAY_MS);
+        self.rst.set_low().map_err(|e| e.kind())?;
+        delay.delay_ms(RESET_DELAY_MS);
+        self.rst.set_high().map_err(|e| e.kind())?;
+        delay.delay_ms(RESET_DELAY_MS);
+        Ok(())
+    }
+
+    /// Power on sequence: reset pulse, initialise, turn on VCC and wait for it to settle,
+    /// then turn on the display.
+    pub fn power_on<DI, D>(&mut self, display: &mut Display<DI>, delay: &mut D) -> Result<(), Error<ErrorKind>>
+    where
+        DI: WriteOnlyDataCommand,
+        D: DelayNs,
+    {
+        self.reset(delay).map_err(Error::Pin)?;
+        display.init().map_err(Error::with_pin)?;
+
+        self.vcc_en.set_high().map_err(|e| Error::Pin(e.kind()))?;
+        delay.delay_ms(VCC_ON_DELAY_MS);
+
+        display.on().map_err(Error::with_pin)
+    }
+
+    /// Power off sequence: turn off the display, turn off VCC and wait for the panel to discharge.
+    /// VCI may be removed when this returns.
+    pub fn power_off<DI, D>(&mut self, display: &mut Display<DI>, delay: &mut D) -> Result<(), Error<ErrorKind>>
+    where
+        DI: WriteOnlyDataCommand,
+        D: DelayNs,
+    {
+        display.off().map_err(Error::with_pin)?;
+
+        self.vcc_en.set_low().map_err(|e| Error::Pin(e.kind()))?;
+        delay.delay_ms(VCC_OFF_DELAY_MS);
+        Ok(())
+    }
+
+    /// Give back the reset and VCC enable pins
+    pub fn release(self) -> (RST, VCC) {
+        (self.rst, self.vcc_en)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::DisplayRotation;
+    use core::cell::RefCell;
+    use core::convert::Infallible;
+    use embedded_hal::digital::ErrorType;
+    use display_interface::{DataFormat, DisplayError};
+    use std::{string::String, vec::Vec, format};
+
+    type Log<'a> = &'a RefCell<Vec<String>>;
+
+    struct Pin<'a>(&'static str, Log<'a>);
+
+    impl ErrorType for Pin<'_> {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for Pin<'_> {
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.1.borrow_mut().push(format!("{} low", self.0));
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            self.1.borrow_mut().push(format!("{} high", self.0));
+            Ok(())
+        }
+    }
+
+    /// Pin of a different type that always fails, e.g. on a disconnected I/O expander
+    struct BrokenPin;
+
+    impl ErrorType for BrokenPin {
+        type Error = ErrorKind;
+    }
+
+    impl OutputPin for BrokenPin {
+        fn set_low(&mut self) -> Result<(), ErrorKind> {
+            Err(ErrorKind::Other)
+        }
+
+        fn set_high(&mut self) -> Result<(), ErrorKind> {
+            Err(ErrorKind::Other)
+        }
+    }
+
+    struct Delay<'a>(Log<'a>);
+
+    impl DelayNs for Delay<'_> {
+        fn delay_ns(&mut self, ns: u32) {
+            self.0.borrow_mut().push(format!("wait {}ns", ns));
+        }
+
+        fn delay_ms(&mut self, ms: u32) {
+            self.0.borrow_mut().push(format!("wait {}", ms));
+        }
+    }
+
+    /// Only logs the display on/off commands
+    struct Interface<'a>(Log<'a>);
+
+    impl WriteOnlyDataCommand for Interface<'_> {
+        fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+            match cmd {
+                DataFormat::U8([0xAE]) => self.0.borrow_mut().push(String::from("display off")),
+                DataFormat::U8([0xAF]) => self.0.borrow_mut().push(String::from("display on")),
+                _ => {}
+            }
+            Ok(())
+        }
+
+        fn send_data(&mut self, _buf: DataFormat<'_>) -> Result<(), DisplayError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn it_powers_on_and_off_in_order() {
+        let log = RefCell::new(Vec::new());
+        let mut display = Display::new(Interface(&log), DisplayRotation::Rotate0);
+        let mut power = PowerControl::new(Pin("rst", &log), Pin("vcc", &log));
+        let mut delay = Delay(&log);
+
+        power.power_on(&mut display, &mut delay).unwrap();
+        assert_eq!(
+            log.replace(Vec::new()),
+            [
+                "vcc low", "rst high", "wait 1", "rst low", "wait 1", "rst high", "wait 1",
+                "vcc high", "wait 100", "display on"
+            ]
+        );
+
+        power.power_off(&mut display, &mut delay).unwrap();
+        assert_eq!(log.replace(Vec::new()), ["display off", "vcc low", "wait 100"]);
+    }
+
+    #[test]
+    fn it_reports_errors_of_either_pin() {
+        let log = RefCell::new(Vec::new());
+        let mut display = Display::new(Interface(&log), DisplayRotation::Rotate0);
+        let mut power = PowerControl::new(Pin("rst", &log), BrokenPin);
+
+        let result = power.power_on(&mut display, &mut Delay(&log));
+        assert!(matches!(result, Err(Error::Pin(ErrorKind::Other))));
+        // VCC is switched off first, nothing else happens when that fails
+        assert!(log.borrow().is_empty());
+    }
+}
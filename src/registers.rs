@@ -0,0 +1,330 @@
+//! Shadow copy of the controller registers
+//!
+//! The SSD1362 can't be read back over SPI or I2C. `Display` records every command it sends
+//! in `Registers`, so the state of the controller can be inspected and sent again, e.g. after
+//! an ESD glitch or a brown-out corrupted it. See `Display::registers` and `Display::reapply_state`.
+//!
+//! Registers that were never sent are `None` and keep the reset value of the controller.
+//! To keep the state while the bus is used for something else, take the registers along with
+//! the interface from `Display::into_parts` and give them back to `Display::with_registers`.
+
+use crate::command::{Command, DisplayMode, FadeMode, RemapConfig, ScrollDirection, ScrollInterval, VcomhLevel};
+use crate::framebuffer::{RAM_COLUMNS, RAM_ROWS};
+
+/// Horizontal scroll setup: direction, rows (start, end), columns (start, end) and interval
+pub type HScrollSetup = (ScrollDirection, (u8, u8), (u8, u8), ScrollInterval);
+
+/// Commands to restore the configuration, see `Registers::config_commands`
+pub(crate) type ConfigCommands = [Option<Command>; 21];
+
+/// Commands to finish a restore, see `Registers::resume_commands`
+pub(crate) type ResumeCommands = [Option<Command>; 4];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Registers {
+    column_window: (u8, u8),
+    row_window: (u8, u8),
+    contrast: u8,
+    remap: RemapConfig,
+    start_line: u8,
+    display_offset: u8,
+    vscroll_area: Option<(u8, u8)>,
+    hscroll: Option<HScrollSetup>,
+    scrolling: bool,
+    mode: DisplayMode,
+    multiplex: u8,
+    internal_vdd: Option<bool>,
+    internal_iref: Option<bool>,
+    on: bool,
+    phase_length: Option<u8>,
+    clock_div: Option<(u8, u8)>,
+    precharge_period: Option<u8>,
+    grayscale: Option<[u8; 15]>,
+    precharge_voltage: Option<u8>,
+    precharge_capacitor: Option<bool>,
+    vcomh: Option<VcomhLevel>,
+    locked: bool,
+    fade: Option<(FadeMode, u8)>,
+    /// The init sequence was recorded, so the state describes a configured controller
+    initialized: bool,
+}
+
+impl Default for Registers {
+    /// Reset state of the controller
+    fn default() -> Self {
+        Registers {
+            column_window: (0, RAM_COLUMNS as u8 - 1),
+            row_window: (0, RAM_ROWS as u8 - 1),
+            contrast: 0x7F,
+            remap: RemapConfig::default(),
+            start_line: 0,
+            display_offset: 0,
+            vscroll_area: None,
+            hscroll: None,
+            scrolling: false,
+            mode: DisplayMode::Normal,
+            multiplex: RAM_ROWS as u8 - 1,
+            internal_vdd: None,
+            internal_iref: None,
+            on: false,
+            phase_length: None,
+            clock_div: None,
+            precharge_period: None,
+            grayscale: None,
+            precharge_voltage: None,
+            precharge_capacitor: None,
+            vcomh: None,
+            locked: false,
+            fade: None,
+            initialized: false,
+        }
+    }
+}
+
+impl Registers {
+    /// Column window (start, end) for horizontal or vertical addressing, the end is inclusive
+    pub fn column_window(&self) -> (u8, u8) {
+        self.column_window
+    }
+
+    /// Row window (start, end) for horizontal or vertical addressing, the end is inclusive
+    pub fn row_window(&self) -> (u8, u8) {
+        self.row_window
+    }
+
+    pub fn contrast(&self) -> u8 {
+        self.contrast
+    }
+
+    pub fn remap(&self) -> RemapConfig {
+        self.remap
+    }
+
+    pub fn start_line(&self) -> u8 {
+        self.start_line
+    }
+
+    pub fn display_offset(&self) -> u8 {
+        self.display_offset
+    }
+
+    /// Rows above the vertical scroll area and rows in it
+    pub fn vscroll_area(&self) -> Option<(u8, u8)> {
+        self.vscroll_area
+    }
+
+    /// Last horizontal scroll setup
+    pub fn hscroll(&self) -> Option<HScrollSetup> {
+        self.hscroll
+    }
+
+    pub fn is_scrolling(&self) -> bool {
+        self.scrolling
+    }
+
+    pub fn mode(&self) -> DisplayMode {
+        self.mode
+    }
+
+    /// Multiplex ratio, number of COM lines - 1
+    pub fn multiplex(&self) -> u8 {
+        self.multiplex
+    }
+
+    pub fn internal_vdd(&self) -> Option<bool> {
+        self.internal_vdd
+    }
+
+    pub fn internal_iref(&self) -> Option<bool> {
+        self.internal_iref
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    pub fn phase_length(&self) -> Option<u8> {
+        self.phase_length
+    }
+
+    /// Oscillator frequency and divide ratio - 1
+    pub fn clock_div(&self) -> Option<(u8, u8)> {
+        self.clock_div
+    }
+
+    pub fn precharge_period(&self) -> Option<u8> {
+        self.precharge_period
+    }
+
+    /// Pulse widths of the gray scale table, `None` for the default linear table
+    pub fn grayscale(&self) -> Option<[u8; 15]> {
+        self.grayscale
+    }
+
+    pub fn precharge_voltage(&self) -> Option<u8> {
+        self.precharge_voltage
+    }
+
+    pub fn precharge_capacitor(&self) -> Option<bool> {
+        self.precharge_capacitor
+    }
+
+    pub fn vcomh(&self) -> Option<VcomhLevel> {
+        self.vcomh
+    }
+
+    /// True when the command interface is locked
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Fade or blink mode and its interval
+    pub fn fade(&self) -> Option<(FadeMode, u8)> {
+        self.fade
+    }
+
+    /// True once the init sequence was recorded. Before that the shadow copy only holds
+    /// reset values, which must not be sent to a configured controller.
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
+    /// Record that the complete init sequence was sent
+    pub(crate) fn mark_initialized(&mut self) {
+        self.initialized = true;
+    }
+
+    /// Update the shadow copy with a command that was sent
+    pub(crate) fn record(&mut self, command: Command) {
+        match command {
+            Command::ColumnAddress(start, end) => self.column_window = (start, end),
+            Command::RowAddress(start, end) => self.row_window = (start, end),
+            Command::Contrast(contrast) => self.contrast = contrast,
+            Command::Remap(remap) => self.remap = remap,
+            Command::StartLine(line) => self.start_line = line,
+            Command::DisplayOffset(offset) => self.display_offset = offset,
+            Command::VScrollArea(above, lines) => self.vscroll_area = Some((above, lines)),
+            Command::HScrollSetup(direction, row_start, row_end, col_start, col_end, interval) => {
+                self.hscroll = Some((direction, (row_start, row_end), (col_start, col_end), interval))
+            }
+            Command::ScrollActive(active) => self.scrolling = active,
+            Command::Mode(mode) => self.mode = mode,
+            Command::Multiplex(ratio) => self.multiplex = ratio,
+            Command::InternalVDD(internal) => self.internal_vdd = Some(internal),
+            Command::InternalIREF(internal) => self.internal_iref = Some(internal),
+            Command::DisplayOn(on) => self.on = on,
+            Command::PhaseLength(length) => self.phase_length = Some(length),
+            Command::DisplayClockDiv(fosc, divide) => self.clock_div = Some((fosc, divide)),
+            Command::PreChargePeriod(period) => self.precharge_period = Some(period),
+            Command::GrayScale(table) => self.grayscale = Some(table),
+            Command::DefaultGrayScale() => self.grayscale = None,
+            Command::PreChargeVoltage(level) => self.precharge_voltage = Some(level),
+            Command::PreChargeCapacitor(external) => self.precharge_capacitor = Some(external),
+            Command::VcomhDeselect(level) => self.vcomh = Some(level),
+            Command::CommandLock(lock) => self.locked = lock,
+            Command::FadeBlink(mode, interval) => self.fade = Some((mode, interval)),
+        }
+    }
+
+    /// Commands that restore the configuration, before the display RAM may be written:
+    /// unlocked first and a running scroll stopped. Re-sending the fade setup restarts a running
+    /// fade, so it is only included with `animations`.
+    /// Send `resume_commands` afterwards, they are only valid together.
+    pub(crate) fn config_commands(&self, animations: bool) -> ConfigCommands {
+        [
+            Some(Command::CommandLock(false)).filter(|_| self.locked),
+            Some(Command::ScrollActive(false)).filter(|_| self.scrolling),
+            self.internal_vdd.map(Command::InternalVDD),
+            self.internal_iref.map(Command::InternalIREF),
+            Some(Command::ColumnAddress(self.column_window.0, self.column_window.1)),
+            Some(Command::RowAddress(self.row_window.0, self.row_window.1)),
+            Some(Command::Remap(self.remap)),
+            Some(Command::StartLine(self.start_line)),
+            Some(Command::DisplayOffset(self.display_offset)),
+            self.vscroll_area.map(|(above, lines)| Command::VScrollArea(above, lines)),
+            Some(Command::Mode(self.mode)),
+            Some(Command::Multiplex(self.multiplex)),
+            self.phase_length.map(Command::PhaseLength),
+            self.clock_div.map(|(fosc, divide)| Command::DisplayClockDiv(fosc, divide)),
+            self.precharge_period.map(Command::PreChargePeriod),
+            Some(self.grayscale.map_or(Command::DefaultGrayScale(), Command::GrayScale)),
+            self.precharge_voltage.map(Command::PreChargeVoltage),
+            self.precharge_capacitor.map(Command::PreChargeCapacitor),
+            self.vcomh.map(Command::VcomhDeselect),
+            Some(Command::Contrast(self.contrast)),
+            self.fade.map(|(mode, interval)| Command::FadeBlink(mode, interval)).filter(|_| animations),
+        ]
+    }
+
+    /// Commands that finish a restore after `config_commands` and writing the display RAM:
+    /// scroll setup while scrolling is stopped, display on and lock last.
+    /// A scroll that was running is started again. The setup of a stopped scroll is only
+    /// included with `animations`.
+    pub(crate) fn resume_commands(&self, animations: bool) -> ResumeCommands {
+        let hscroll = self.hscroll.map(|(direction, rows, columns, interval)| {
+            Command::HScrollSetup(direction, rows.0, rows.1, columns.0, columns.1, interval)
+        });
+
+        [
+            hscroll.filter(|_| animations || self.scrolling),
+            Some(Command::ScrollActive(true)).filter(|_| self.scrolling),
+            Some(Command::DisplayOn(self.on)),
+            Some(Command::CommandLock(true)).filter(|_| self.locked),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn it_restores_what_was_recorded() {
+        let mut registers = Registers::default();
+        let sent = [
+            Command::CommandLock(true),
+            Command::InternalVDD(true),
+            Command::Contrast(0x30),
+            Command::DisplayOffset(12),
+            Command::GrayScale([3; 15]),
+            Command::HScrollSetup(ScrollDirection::Left, 0, 7, 10, 20, ScrollInterval::Frames5),
+            Command::ScrollActive(true),
+            Command::FadeBlink(FadeMode::Blink, 2),
+            Command::DisplayOn(true),
+        ];
+        sent.iter().for_each(|command| registers.record(*command));
+
+        assert!(registers.is_locked() && registers.is_on() && registers.is_scrolling());
+        assert_eq!(registers.contrast(), 0x30);
+        assert_eq!(registers.display_offset(), 12);
+        assert_eq!(registers.internal_iref(), None);
+
+        let commands = |registers: &Registers, animations| -> Vec<Command> {
+            let config = registers.config_commands(animations);
+            let resume = registers.resume_commands(animations);
+            config.iter().chain(resume.iter()).flatten().copied().collect()
+        };
+
+        // replaying the commands on a controller in its reset state ends in the same state
+        let mut restored = Registers::default();
+        commands(&registers, true).into_iter().for_each(|command| restored.record(command));
+        assert_eq!(restored, registers);
+
+        let all = commands(&registers, true);
+        assert!(matches!(all.first(), Some(Command::CommandLock(false))));
+        assert!(matches!(all.last(), Some(Command::CommandLock(true))));
+
+        // a running scroll is stopped for the RAM upload and started again, the fade is left alone
+        let animation = |command: &&Command| {
+            matches!(command, Command::ScrollActive(_) | Command::HScrollSetup(..) | Command::FadeBlink(..))
+        };
+        assert_eq!(all.iter().filter(animation).count(), 4);
+        assert!(matches!(registers.config_commands(false)[1], Some(Command::ScrollActive(false))));
+        assert_eq!(commands(&registers, false).iter().filter(animation).count(), 3);
+
+        // nothing to stop when nothing was scrolling
+        let registers = Registers::default();
+        assert_eq!(commands(&registers, true).iter().filter(animation).count(), 0);
+    }
+}
@@ -91,15 +91,22 @@ impl<N: ArrayLength<u8>> Ringbuffer<N> {
         Ok(())
     }
 
-    pub fn add(&mut self, bytes: &[u8]) {
+    /// Add an element, pops the oldest elements until it fits.
+    /// Returns false, without popping anything, when it does not even fit into the empty buffer.
+    pub fn add(&mut self, bytes: &[u8]) -> bool {
+        if bytes.len() > self.capacity() {
+            return false;
+        }
+
         while let Err(_err) = self.try_add(bytes) {
             #[cfg(test)]
             println!("No space, will pop 1 element");
             if !self.pop() {
                 // nothing to pop anymore
-                break;
+                return false;
             }
         }
+        true
     }
 
     pub fn iter(&self, offset: usize) -> impl Iterator<Item=&[u8]> {
@@ -206,6 +213,12 @@ impl<N: ArrayLength<u8>> Ringbuffer<N> {
         };
     }
 
+    /// Size of the largest element that fits into the empty buffer
+    pub fn capacity(&self) -> usize {
+        // one byte less, see `free`
+        self.buffer.len() - 1
+    }
+
     /// Get number of free elements in ringbuffer
     pub fn free(&self) -> usize {
         let first_index: usize = match self.line_pointers.front() {
@@ -230,9 +243,6 @@ impl<N: ArrayLength<u8>> Ringbuffer<N> {
     }
 }
 
-#[cfg(test)]
-#[macro_use]
-extern crate std;
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -248,6 +258,18 @@ mod tests {
         assert_eq!(buffer.free(), 0);
     }
 
+    #[test]
+    fn it_keeps_elements_when_adding_too_much() {
+        let mut buffer: Ringbuffer::<U8> = Ringbuffer::new();
+        assert!(buffer.add(&[1, 2]));
+
+        assert!(!buffer.add(&[0; 8]));
+        assert_eq!(buffer.element(0), Some(&[1u8, 2][..]));
+
+        assert!(buffer.add(&[0; 7]));
+        assert_eq!(buffer.element(0), Some(&[0u8; 7][..]));
+    }
+
     #[test]
     fn it_knows_its_length() {
 
@@ -1,8 +1,9 @@
-use display_interface::{DisplayError, WriteOnlyDataCommand};
+use display_interface::WriteOnlyDataCommand;
 use core::{cmp::min, fmt};
 
 pub use crate::terminal::chars::{Font6x8, TerminalFont};
 use crate::display::Display;
+use crate::error::Error;
 
 use heapless::consts::U1024 as BUFFERSIZE;
 
@@ -59,17 +60,15 @@ impl Cursor {
         CursorWrapEvent(self.row)
     }
 
-    pub fn get_line_box(&self, offset: usize) -> ((u8, u8), (u8, u8)) {
-        let (chr_w,chr_h) = self.char_size;
-
-        let x_end = self.width * chr_w / 2;
+    /// Gets the top left pixel of the line the cursor is on, moved down by `offset` lines
+    pub fn get_line_origin(&self, offset: usize) -> (usize, usize) {
+        let (_, chr_h) = self.char_size;
 
         // TODO this can become negative if self.row => self.height
         // how to handle
         let y_start = (self.height - 1) * chr_h - (self.row - offset) * chr_h;
-        let y_end = y_start + chr_h;
 
-        ((0u8, y_start as u8), (x_end as u8, y_end as u8))
+        (0, y_start)
     }
 
     /// Sets the position of the logical cursor arbitrarily.
@@ -94,6 +93,8 @@ struct RenderEngine<DI, F> {
     display: Display<DI>,
     font:  F,
     cursor: Cursor,
+    /// Pixel position where the next character is drawn
+    pen: (usize, usize),
     wrap: bool,
     num_lines: usize
 }
@@ -112,12 +113,13 @@ where
             display,
             font,
             cursor,
+            pen: (0, 0),
             wrap,
             num_lines
         }
     }
 
-    pub fn init(&mut self) -> Result<(), DisplayError> {
+    pub fn init(&mut self) -> Result<(), Error> {
 
         self.display.init()?;
         self.display.on()?;
@@ -125,20 +127,17 @@ where
         Ok(())
     }
 
-    pub fn clear(&mut self) -> Result<(), DisplayError> {
+    pub fn clear(&mut self) -> Result<(), Error> {
 
-        self.display.set_draw_area((0,0), (128, 64))?;
-        let buffer: [u8; 128] = [0u8; 128];
-        for _i in 0..64 {
-            self.display.draw(&buffer)?;
-        }
+        self.display.clear(0);
+        self.display.flush()?;
         self.cursor.set_position(0,0);
 
         Ok(())
     }
 
     /// Renders the full screen at once
-    fn render_all<'a>(&mut self, lines: impl Iterator<Item=&'a[u8]>) -> Result<(), DisplayError> {
+    fn render_all<'a>(&mut self, lines: impl Iterator<Item=&'a[u8]>) -> Result<(), Error> {
 
         self.cursor.set_position(0,0);
 
@@ -151,8 +150,8 @@ where
             };
 
             // Check if element needs more than 1 line
-            let extra_lines = if self.wrap {
-                line_length / (self.cursor.width + 1)
+            let extra_lines = if self.wrap && line_length > 0 {
+                (line_length - 1) / self.cursor.width
             } else {
                 0
             };
@@ -170,24 +169,23 @@ where
 
             let mut line_offset = 0;
 
-            let draw_area = self.cursor.get_line_box(line_offset);
-            self.display.set_draw_area(draw_area.0, draw_area.1)?;
+            self.pen = self.cursor.get_line_origin(line_offset);
 
-            for byte in line {
+            for (index, byte) in line.iter().enumerate() {
 
                 if *byte as char == '\n' {
                     break;
                 }
 
-                self.write_char(*byte as char)?;
+                self.write_char(*byte as char);
 
 
                 if let Some(_wrap) = self.cursor.advance() {
-                    if self.wrap && (line_length > self.cursor.width) {
+                    // only start another row if characters are left for it
+                    if self.wrap && (index + 1 < line_length) {
                         line_offset += 1;
                         self.cursor.set_position(0, self.cursor.get_position().1);
-                        let draw_area = self.cursor.get_line_box(line_offset);
-                        self.display.set_draw_area(draw_area.0, draw_area.1)?;
+                        self.pen = self.cursor.get_line_origin(line_offset);
                     } else {
                         // no wrap, go to next line
                         break;
@@ -196,7 +194,7 @@ where
 
             }
 
-            self.fill_blank()?;
+            self.fill_blank();
             self.cursor.advance_line();
 
             if self.cursor.get_position().1 >= self.num_lines {
@@ -206,45 +204,54 @@ where
 
         // clear rest of screen in case there was old data that has been removed from buffer
         while !self.cursor.is_at_end() {
-            let draw_area = self.cursor.get_line_box(0);
-            self.display.set_draw_area(draw_area.0, draw_area.1)?;
-            self.fill_blank()?;
+            self.pen = self.cursor.get_line_origin(0);
+            self.fill_blank();
             self.cursor.advance_line();
         }
 
-        Ok(())
+        // only the pixels that changed since the last render are sent
+        self.display.flush()
     }
 
-    fn fill_blank(&mut self) -> Result<(), DisplayError> {
+    fn fill_blank(&mut self) {
         if self.cursor.get_position().0 == self.cursor.width {
-            return Ok(());
+            return;
         }
         loop {
-            self.write_char(' ')?;
+            self.write_char(' ');
             if let Some(_wrap) = self.cursor.advance() {
                 break;
             }
         }
-        Ok(())
     }
 
-    fn write_char(&mut self, chr: char) -> Result<(), DisplayError> {
+    fn write_char(&mut self, chr: char) {
 
         match chr {
-            '\t' => self.draw_char(' ')?,
+            '\t' => self.draw_char(' '),
             '\n' =>  {},
             '\r' => {},
             '\0' => {},
-            _ => self.draw_char(chr)?
+            _ => self.draw_char(chr)
         }
-
-        Ok(())
     }
 
-    fn draw_char(&mut self, chr: char) -> Result<(), DisplayError> {
+    /// Draws a character into the framebuffer at the pen position and moves the pen to the right
+    fn draw_char(&mut self, chr: char) {
+        let (chr_w, chr_h) = self.font.char_size();
         let bitmap = self.font.get_char(chr as u8);
-        self.display.draw(&bitmap)?;
-        Ok(())
+        let (x0, y0) = self.pen;
+
+        // the bitmap is stored column by column with two pixels per byte, left pixel in the high nibble
+        for x in 0..chr_w {
+            for y in 0..chr_h {
+                let byte = bitmap[(x / 2) * chr_h + y];
+                let level = if x & 1 == 0 { byte >> 4 } else { byte & 0x0F };
+                self.display.set_pixel(x0 + x, y0 + y, level);
+            }
+        }
+
+        self.pen.0 += chr_w;
     }
 }
 
@@ -268,25 +275,41 @@ where
         }
     }
 
-    pub fn init(&mut self) -> Result<(), DisplayError> {
+    pub fn init(&mut self) -> Result<(), Error> {
         self.render.init()?;
         Ok(())
     }
 
-    pub fn write_string(&mut self, s: &str) -> Result<(), DisplayError> {
+    /// Add text to the terminal, the oldest lines are dropped when the buffer is full.
+    /// Fails with `Error::BufferOverflow` when the text does not fit into the empty buffer.
+    pub fn write_string(&mut self, s: &str) -> Result<(), Error> {
 
-        self.char_buffer.add(s.as_bytes());
+        if !self.char_buffer.add(s.as_bytes()) {
+            return Err(Error::BufferOverflow);
+        }
 
         Ok(())
     }
 
-    pub fn render(&mut self) -> Result<(), DisplayError> {
+    /// Draw the text and flush it to the display.
+    /// Fails with `Error::Busy` while the display scrolls horizontally, see `Display::flush`.
+    pub fn render(&mut self) -> Result<(), Error> {
         self.render.render_all(self.char_buffer.reverse_iter(self.scroll_offset))
     }
 
     pub fn set_scroll_offset(&mut self, offset: usize) {
         self.scroll_offset = offset;
     }
+
+    /// See `Display::refresh_config`
+    pub fn refresh_config(&mut self) -> Result<(), Error> {
+        self.render.display.refresh_config()
+    }
+
+    /// Give back the display, the text in the terminal is dropped
+    pub fn release(self) -> Display<DI> {
+        self.render.display
+    }
 }
 
 impl<DI, F> fmt::Write for TerminalView<DI, F>
@@ -295,7 +318,78 @@ where
     F: TerminalFont
 {
     fn write_str(&mut self, s: &str) -> Result<(), fmt::Error> {
-        self.write_string(s).unwrap(); // todo error .map_err(err)
-        Ok(())
+        self.write_string(s).map_err(|_| fmt::Error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::DisplayRotation;
+    use display_interface::{DataFormat, DisplayError};
+
+    /// Interface that accepts and drops everything
+    struct NullInterface;
+
+    impl WriteOnlyDataCommand for NullInterface {
+        fn send_commands(&mut self, _cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+            Ok(())
+        }
+
+        fn send_data(&mut self, _buf: DataFormat<'_>) -> Result<(), DisplayError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn it_renders_in_portrait() {
+        let display = Display::new(NullInterface, DisplayRotation::Rotate90);
+        let mut terminal = TerminalView::new(display, Font6x8 {});
+        terminal.init().unwrap();
+
+        assert_eq!(terminal.render.cursor.get_dimensions(), (8, 32));
+
+        terminal.write_string("A").unwrap();
+        terminal.render().unwrap();
+
+        // newest line is at the bottom, top row of 'A' is 0b001110 shifted by one pixel
+        let display = &terminal.render.display;
+        let row: std::vec::Vec<u8> = (0..8).map(|x| display.get_pixel(x, 248).unwrap()).collect();
+        assert_eq!(row, [0, 0, 0, 15, 15, 15, 0, 0]);
+    }
+
+    #[test]
+    fn it_reports_text_that_does_not_fit() {
+        use core::fmt::Write;
+
+        let display = Display::new(NullInterface, DisplayRotation::Rotate0);
+        let mut terminal = TerminalView::new(display, Font6x8 {});
+        terminal.write_string("A").unwrap();
+
+        let text = "x".repeat(2000);
+        assert!(matches!(terminal.write_string(&text), Err(Error::BufferOverflow)));
+        assert_eq!(terminal.write_str(&text), Err(fmt::Error));
+
+        // the earlier text is still there
+        terminal.render().unwrap();
+        let display = &terminal.render.display;
+        let row: std::vec::Vec<u8> = (0..8).map(|x| display.get_pixel(x, 56).unwrap()).collect();
+        assert_eq!(row, [0, 0, 0, 15, 15, 15, 0, 0]);
+
+        assert!(terminal.write_str("fits").is_ok());
+    }
+
+    #[test]
+    fn it_releases_the_display() {
+        let display = Display::new(NullInterface, DisplayRotation::Rotate0);
+        let mut terminal = TerminalView::new(display, Font6x8 {});
+        terminal.write_string("A").unwrap();
+        terminal.render().unwrap();
+
+        // the rendered text stays in the framebuffer
+        let display = terminal.release();
+        let row: std::vec::Vec<u8> = (0..8).map(|x| display.get_pixel(x, 56).unwrap()).collect();
+        assert_eq!(row, [0, 0, 0, 15, 15, 15, 0, 0]);
+        let NullInterface = display.release();
     }
 }
@@ -0,0 +1,177 @@
+//! Golden image tests for `TerminalView` rendering
+//!
+//! Every test renders into the emulated controller and compares the screen with a reference
+//! bitmap in `tests/golden`. The bitmaps are text, one character per pixel: `.` for off and the
+//! hex gray level otherwise, so changes show up readable in a diff.
+//!
+//! After an intended change in rendering, regenerate the references with
+//!
+//! ```text
+//! UPDATE_GOLDEN=1 cargo test --target x86_64-unknown-linux-gnu --features std --test terminal_golden
+//! ```
+//! and review the diff.
+
+use std::cell::RefCell;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use ssd1362::display::{Display, DisplayRotation};
+use ssd1362::emulator::Emulator;
+use ssd1362::terminal::{Font6x8, TerminalView};
+
+/// Passes everything on to an emulator that the test keeps a handle to
+#[derive(Clone, Default)]
+struct Recording(Rc<RefCell<Emulator>>);
+
+impl WriteOnlyDataCommand for Recording {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.0.borrow_mut().send_commands(cmd)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.0.borrow_mut().send_data(buf)
+    }
+}
+
+fn terminal() -> (TerminalView<Recording, Font6x8>, Recording) {
+    let recording = Recording::default();
+    let display = Display::new(recording.clone(), DisplayRotation::Rotate0);
+
+    let mut terminal = TerminalView::new(display, Font6x8 {});
+    terminal.init().unwrap();
+    (terminal, recording)
+}
+
+/// Add a line to the terminal in one piece. `writeln!` with arguments hands the text over in
+/// several parts, which the terminal buffer keeps as separate lines.
+fn write_line(terminal: &mut TerminalView<Recording, Font6x8>, line: &str) {
+    terminal.write_string(&format!("{}\n", line)).unwrap();
+}
+
+/// The screen as text, one line per row
+fn screen_text(emulator: &Emulator) -> String {
+    let (width, height) = emulator.screen_size();
+    let mut text = String::with_capacity((width + 1) * height);
+
+    for y in 0..height {
+        for x in 0..width {
+            match emulator.screen_pixel(x, y) {
+                0 => text.push('.'),
+                level => write!(text, "{:X}", level).unwrap(),
+            }
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Compare the screen with `tests/golden/<name>.txt`
+fn assert_screen(recording: &Recording, name: &str) {
+    let emulator = recording.0.borrow();
+    let actual = screen_text(&emulator);
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}.txt", name));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("cannot read {}: {}, run with UPDATE_GOLDEN=1 to create it", path.display(), e));
+
+    if actual != expected {
+        let png = std::env::temp_dir().join(format!("{}.png", name));
+        std::fs::write(&png, emulator.to_png()).unwrap();
+
+        let row = actual.lines().zip(expected.lines()).position(|(a, e)| a != e);
+        panic!(
+            "screen differs from {} (first in row {:?}), actual screen written to {}",
+            path.display(),
+            row,
+            png.display()
+        );
+    }
+}
+
+#[test]
+fn it_renders_lines_from_the_bottom() {
+    let (mut terminal, recording) = terminal();
+
+    write_line(&mut terminal, "first line");
+    write_line(&mut terminal, "second line");
+    terminal.render().unwrap();
+
+    assert_screen(&recording, "terminal_lines");
+}
+
+#[test]
+fn it_wraps_long_lines() {
+    let (mut terminal, recording) = terminal();
+
+    write_line(&mut terminal, "short");
+    // 32 characters of the 8 pixel wide font fit on a line
+    write_line(&mut terminal, "This line is longer than the 32 characters of one line");
+    terminal.render().unwrap();
+
+    assert_screen(&recording, "terminal_wrap");
+}
+
+#[test]
+fn it_wraps_lines_that_fill_whole_rows() {
+    let (mut terminal, recording) = terminal();
+
+    write_line(&mut terminal, "short");
+    // exactly two rows, and two rows with one character on a third
+    write_line(&mut terminal, &"ab".repeat(32));
+    write_line(&mut terminal, &format!("{}c", "01".repeat(32)));
+    write_line(&mut terminal, "last");
+    terminal.render().unwrap();
+
+    assert_screen(&recording, "terminal_wrap_rows");
+}
+
+#[test]
+fn it_scrolls_back() {
+    let (mut terminal, recording) = terminal();
+
+    for i in 0..12 {
+        write_line(&mut terminal, &format!("line {}", i));
+    }
+    terminal.render().unwrap();
+    assert_screen(&recording, "terminal_scroll_0");
+
+    terminal.set_scroll_offset(3);
+    terminal.render().unwrap();
+    assert_screen(&recording, "terminal_scroll_3");
+}
+
+#[test]
+fn it_stops_at_lines_longer_than_the_screen() {
+    let (mut terminal, recording) = terminal();
+
+    write_line(&mut terminal, "before");
+    // does not fit on the screen, neither it nor anything older is shown
+    write_line(&mut terminal, &"0123456789".repeat(40));
+    write_line(&mut terminal, "after");
+    terminal.render().unwrap();
+
+    assert_screen(&recording, "terminal_over_long");
+}
+
+#[test]
+fn it_blanks_what_is_no_longer_shown() {
+    let (mut terminal, recording) = terminal();
+
+    for i in 0..4 {
+        write_line(&mut terminal, &format!("{} {}", i, "#".repeat(40)));
+    }
+    terminal.render().unwrap();
+    assert_screen(&recording, "terminal_blank_before");
+
+    // scrolled back past the two newest lines, the bottom of the screen has to be cleared
+    terminal.set_scroll_offset(2);
+    terminal.render().unwrap();
+    assert_screen(&recording, "terminal_blank_after");
+}